@@ -0,0 +1,10 @@
+-- Your SQL goes here
+CREATE TABLE "collection_exclusions" (
+    "id" INTEGER NOT NULL UNIQUE,
+	"collection_id"	INTEGER NOT NULL,
+	"kind"	TEXT NOT NULL,
+	"value"	TEXT NOT NULL,
+    PRIMARY KEY("id" AUTOINCREMENT),
+	FOREIGN KEY("collection_id") REFERENCES collections (id),
+	UNIQUE("collection_id", "kind", "value")
+);
@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE "collection_dependencies" ADD COLUMN "filter" TEXT;
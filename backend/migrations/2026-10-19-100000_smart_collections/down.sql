@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE "collections" DROP COLUMN "rule";
+ALTER TABLE "collections" DROP COLUMN "kind";
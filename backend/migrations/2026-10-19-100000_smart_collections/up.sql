@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TABLE "collections" ADD COLUMN "kind" TEXT NOT NULL DEFAULT 'standard';
+ALTER TABLE "collections" ADD COLUMN "rule" TEXT;
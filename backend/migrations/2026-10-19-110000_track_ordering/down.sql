@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE "collections" DROP COLUMN "sort_whole_playlist";
+ALTER TABLE "collections" DROP COLUMN "track_order";
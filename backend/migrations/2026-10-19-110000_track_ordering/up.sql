@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TABLE "collections" ADD COLUMN "track_order" TEXT NOT NULL DEFAULT 'grouped_by_child';
+ALTER TABLE "collections" ADD COLUMN "sort_whole_playlist" BOOLEAN NOT NULL DEFAULT 0;
@@ -0,0 +1,7 @@
+-- Your SQL goes here
+CREATE TABLE "track_isrcs" (
+    "id" INTEGER NOT NULL UNIQUE,
+	"deezer_id"	TEXT NOT NULL UNIQUE,
+	"isrc"	TEXT NOT NULL,
+    PRIMARY KEY("id" AUTOINCREMENT)
+);
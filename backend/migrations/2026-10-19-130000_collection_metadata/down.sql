@@ -0,0 +1,9 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE "collection_tags";
+ALTER TABLE "collections" DROP COLUMN "track_count";
+ALTER TABLE "collections" DROP COLUMN "last_refresh_tracks_added";
+ALTER TABLE "collections" DROP COLUMN "last_refresh_error";
+ALTER TABLE "collections" DROP COLUMN "last_refresh_status";
+ALTER TABLE "collections" DROP COLUMN "last_refreshed_at";
+ALTER TABLE "collections" DROP COLUMN "created_at";
+ALTER TABLE "collections" DROP COLUMN "description";
@@ -0,0 +1,16 @@
+-- Your SQL goes here
+ALTER TABLE "collections" ADD COLUMN "description" TEXT;
+ALTER TABLE "collections" ADD COLUMN "created_at" BIGINT;
+ALTER TABLE "collections" ADD COLUMN "last_refreshed_at" BIGINT;
+ALTER TABLE "collections" ADD COLUMN "last_refresh_status" TEXT;
+ALTER TABLE "collections" ADD COLUMN "last_refresh_error" TEXT;
+ALTER TABLE "collections" ADD COLUMN "last_refresh_tracks_added" INTEGER;
+ALTER TABLE "collections" ADD COLUMN "track_count" INTEGER;
+CREATE TABLE "collection_tags" (
+    "id" INTEGER NOT NULL UNIQUE,
+	"collection_id"	INTEGER NOT NULL,
+	"tag"	TEXT NOT NULL,
+    PRIMARY KEY("id" AUTOINCREMENT),
+	FOREIGN KEY("collection_id") REFERENCES collections (id),
+	UNIQUE("collection_id", "tag")
+);
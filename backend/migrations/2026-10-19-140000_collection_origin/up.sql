@@ -0,0 +1,3 @@
+-- Your SQL goes here
+-- the collections saved before the origin was recorded are considered imported, so that their playlist is never deleted
+ALTER TABLE "collections" ADD COLUMN "origin" TEXT NOT NULL DEFAULT 'imported';
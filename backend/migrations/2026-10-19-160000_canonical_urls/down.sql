@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+-- only the created collections were saved with a locale, the imported ones used the url returned by deezer
+UPDATE "collections" SET "url" = 'https://www.deezer.com/fr/playlist/' || "deezer_id" WHERE "origin" = 'created';
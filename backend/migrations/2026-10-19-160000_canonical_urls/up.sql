@@ -0,0 +1,3 @@
+-- Your SQL goes here
+-- the urls are saved without locale, e.g. https://www.deezer.com/playlist/123456
+UPDATE "collections" SET "url" = 'https://www.deezer.com/playlist/' || "deezer_id";
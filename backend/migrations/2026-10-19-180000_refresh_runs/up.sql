@@ -0,0 +1,9 @@
+-- Your SQL goes here
+CREATE TABLE "refresh_runs" (
+    "id" INTEGER NOT NULL UNIQUE,
+	"started_at"	BIGINT NOT NULL,
+	"finished_at"	BIGINT,
+	"status"	TEXT NOT NULL,
+	"error"	TEXT,
+    PRIMARY KEY("id" AUTOINCREMENT)
+);
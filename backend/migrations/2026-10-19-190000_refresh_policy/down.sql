@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE "collections" DROP COLUMN "refresh_schedule";
+ALTER TABLE "collections" DROP COLUMN "refresh_enabled";
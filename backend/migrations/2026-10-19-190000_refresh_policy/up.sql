@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TABLE "collections" ADD COLUMN "refresh_enabled" BOOLEAN NOT NULL DEFAULT 1;
+ALTER TABLE "collections" ADD COLUMN "refresh_schedule" TEXT NOT NULL DEFAULT 'always';
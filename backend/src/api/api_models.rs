@@ -1,5 +1,11 @@
 use serde::{Deserialize, Serialize};
 
+use crate::domain::domain_models::{
+    CollectionSort, DependencyAction, ExclusionKind, GraphFormat, HierarchyFormat,
+    PlaylistFileFormat, RefreshSchedule, SmartCollectionRule, SortDirection, TrackFilter,
+    TrackOrder, TracklistFormat, TracklistView,
+};
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct InitCollectionInput {
     pub name: String,
@@ -10,6 +16,7 @@ pub struct InitCollectionInput {
 pub struct AddCollectionToParent {
     pub parent_collection_id: String,
     pub child_collection_id: String,
+    pub filter: Option<TrackFilter>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -17,3 +24,132 @@ pub struct RemoveCollectionToParent {
     pub parent_collection_id: String,
     pub child_collection_id: String,
 }
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct SetCollectionDependencyFilter {
+    pub parent_collection_id: String,
+    pub child_collection_id: String,
+    pub filter: Option<TrackFilter>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ExclusionInput {
+    pub kind: ExclusionKind,
+    pub value: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct RefreshCollectionQuery {
+    pub dry_run: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InitSmartCollectionInput {
+    pub name: String,
+    pub rule: SmartCollectionRule,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct SetSmartCollectionRule {
+    pub rule: SmartCollectionRule,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct SetOrderingPolicy {
+    pub track_order: TrackOrder,
+    pub sort_whole_playlist: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct SetRefreshPolicy {
+    pub enabled: Option<bool>,
+    pub schedule: Option<RefreshSchedule>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct SetCollectionMetadata {
+    pub description: Option<String>,
+    pub tags: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ListCollectionsQuery {
+    pub tag: Option<String>,
+    pub search: Option<String>,
+    pub sort: Option<CollectionSort>,
+    pub direction: Option<SortDirection>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct RenameCollection {
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct RemoveCollectionQuery {
+    pub delete_playlist: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct HealthQuery {
+    pub prune: Option<bool>,
+    pub repair: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ImportUserPlaylists {
+    pub playlist_ids: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct EventsQuery {
+    pub since: Option<u64>,
+    pub follow: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct GraphQuery {
+    pub format: Option<GraphFormat>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct TracklistExportQuery {
+    pub format: Option<TracklistFormat>,
+    pub view: Option<TracklistView>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ImportPlaylistFileQuery {
+    pub name: Option<String>,
+    pub format: Option<PlaylistFileFormat>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct HierarchyQuery {
+    pub format: Option<HierarchyFormat>,
+    pub prune: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct DependencyOperationInput {
+    pub action: DependencyAction,
+    pub parent_collection_id: String,
+    pub child_collection_id: String,
+    pub filter: Option<TrackFilter>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct DependencyBatchInput {
+    pub operations: Vec<DependencyOperationInput>,
+    pub allow_cycles: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct MoveCollection {
+    pub child_collection_id: String,
+    pub old_parent_collection_id: String,
+    pub new_parent_collection_id: String,
+    pub refresh: Option<bool>,
+}
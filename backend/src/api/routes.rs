@@ -1,18 +1,75 @@
-use super::api_models::{AddCollectionToParent, InitCollectionInput, RemoveCollectionToParent};
+use super::api_models::{
+    AddCollectionToParent, DependencyBatchInput, EventsQuery, ExclusionInput, GraphQuery, HealthQuery,
+    HierarchyQuery, ImportPlaylistFileQuery, ImportUserPlaylists, InitCollectionInput,
+    InitSmartCollectionInput, ListCollectionsQuery, MoveCollection, RefreshCollectionQuery,
+    RemoveCollectionQuery, RemoveCollectionToParent, RenameCollection,
+    SetCollectionDependencyFilter, SetCollectionMetadata, SetOrderingPolicy, SetRefreshPolicy,
+    SetSmartCollectionRule, TracklistExportQuery,
+};
 use crate::domain::{
-    collection_dependencies::{add_collection_dependency, remove_collection_dependency},
+    collection_dependencies::{
+        add_collection_dependency, remove_collection_dependency,
+        set_collection_dependency_filter as set_collection_dependency_filter_domain,
+    },
+    collection_exclusions::{add_exclusion, get_exclusions, remove_exclusion},
+    collection_health::check_collections_health,
+    collection_hierarchy::{
+        apply_hierarchy as apply_hierarchy_domain, plan_hierarchy as plan_hierarchy_domain,
+    },
+    collection_import::{
+        get_user_playlists as get_user_playlists_domain,
+        import_user_playlists as import_user_playlists_domain,
+    },
     collection_management::{
         clear_data as clear_data_domain, get_collection,
         get_collection_tracks_excluding_children as get_collection_tracks_excluding_children_domain,
         get_direct_children_collections as get_direct_children_collections_domain,
-        init_collections, list_collections, refresh_collection as refresh_collection_domain,
-        remove_collection as remove_collection_domain, update_all_collections,
+        init_collections, list_collections_page, refresh_collection as refresh_collection_domain,
+        refresh_collection_dry_run, remove_collection as remove_collection_domain,
+        remove_collection_and_playlist, update_all_collections,
+    },
+    collection_metadata::set_collection_metadata as set_collection_metadata_domain,
+    collection_refresh_policy::set_refresh_policy,
+    collection_statistics::{
+        get_collection_statistics as get_collection_statistics_domain,
+        get_global_statistics as get_global_statistics_domain,
+    },
+    collection_sync::{
+        rename_collection as rename_collection_domain,
+        sync_collections_from_deezer as sync_collections_from_deezer_domain,
+    },
+    collection_watcher::watch_child_collections as watch_child_collections_domain,
+    dependency_batch::{
+        apply_dependency_batch as apply_dependency_batch_domain,
+        move_collection as move_collection_domain,
+    },
+    domain_models::{
+        CollectionListQuery, DependencyOperation, Exclusion, GraphFormat, InitCollection,
+        OrderingPolicy, TracklistFormat,
     },
-    domain_models::InitCollection,
+    duplicates::get_duplicate_groups,
     errors::DomainError,
+    events::{subscribe_events, PublishedEvent},
+    graph_export::export_collection_graph,
+    playlist_file_import::import_playlist_file as import_playlist_file_domain,
+    refresh_scheduler::get_refresh_runs as get_refresh_runs_domain,
+    smart_collections::{
+        create_smart_collection, set_smart_collection_rule as set_smart_collection_rule_domain,
+    },
+    track_ordering::set_ordering_policy,
+    tracklist_export::{
+        export_collection_tracklist as export_collection_tracklist_domain,
+        get_tracklist_file_extension,
+    },
+};
+use futures::future::Either;
+use log::{info, warn};
+use std::convert::Infallible;
+use tokio_stream::{wrappers::BroadcastStream, StreamExt};
+use warp::{
+    filters::cors::Builder, hyper::body::Bytes, reply::Response, sse::Event, Filter, Rejection,
+    Reply,
 };
-use log::info;
-use warp::{filters::cors::Builder, reply::Response, Filter, Rejection, Reply};
 
 pub fn build_routes() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
     init_collection()
@@ -26,6 +83,34 @@ pub fn build_routes() -> impl Filter<Extract = impl Reply, Error = Rejection> +
         .or(get_collection_tracks_excluding_children())
         .or(get_direct_children_collections())
         .or(clear_data())
+        .or(get_events())
+        .or(get_collection_exclusions())
+        .or(add_collection_exclusion())
+        .or(remove_collection_exclusion())
+        .or(set_collection_dependency_filter())
+        .or(init_smart_collection())
+        .or(set_smart_collection_rule())
+        .or(set_collection_ordering())
+        .or(set_collection_refresh_policy())
+        .or(get_collection_duplicates())
+        .or(set_collection_metadata())
+        .or(rename_collection())
+        .or(sync_collections_from_deezer())
+        .or(get_health_report())
+        .or(fix_health_issues())
+        .or(get_user_playlists())
+        .or(import_user_playlists())
+        .or(get_collection_statistics())
+        .or(get_global_statistics())
+        .or(get_collection_graph())
+        .or(export_collection_tracklist())
+        .or(import_playlist_file())
+        .or(plan_hierarchy())
+        .or(apply_hierarchy())
+        .or(apply_dependency_batch())
+        .or(move_collection())
+        .or(get_refresh_runs())
+        .or(watch_child_collections())
 }
 
 /// POST /collection/init
@@ -37,7 +122,7 @@ pub fn build_routes() -> impl Filter<Extract = impl Reply, Error = Rejection> +
 /// inputs : Json with body
 /// {
 ///     "name":String, // name of the collection. will be ignored if from_playlist is set.
-///     "from_playlist":Option<String> // url of the deezer playlist
+///     "from_playlist":Option<String> // url of the deezer playlist in any locale, deezer.page.link short link, deezer:// uri or id
 /// }
 ///
 /// outputs : empty
@@ -70,27 +155,368 @@ async fn call_init_collection(
     }
 }
 
-/// GET /collection/list
+/// POST /collection/smart
 ///
-/// Returns a list of all the collections saved in the database
+/// initializes a smart collection in deezer and in the database
+/// A smart collection is filled with the tracks of the other collections matching its rule when refreshed
 ///
-/// inputs : empty
+/// inputs : Json with body
+/// {
+///     "name": String, // name of the collection
+///     "rule": {
+///         "source_collections": Option<[String]>, // deezer playlist ids, every other collection if not set
+///         "filter": Option<Filter>, // same as in /collection-management/add-collection
+///         "added_within_days": Option<u64> // only the tracks added in the sources in the last days
+///     }
+/// }
+///
+/// outputs : empty
+pub fn init_smart_collection() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("collection" / "smart")
+        .and(warp::post())
+        .and(warp::body::json()) //JSON body
+        .and(warp::body::content_length_limit(1024 * 16)) // Avoids huge payloads
+        .and_then(call_init_smart_collection)
+        .with(&get_cors_config())
+}
+
+async fn call_init_smart_collection(
+    init_smart_collection_input: InitSmartCollectionInput,
+) -> Result<Response, Rejection> {
+    info!(
+        "initializing smart collection {}",
+        init_smart_collection_input.name
+    );
+    match create_smart_collection(
+        init_smart_collection_input.name.as_str(),
+        init_smart_collection_input.rule,
+    )
+    .await
+    {
+        Ok(_) => {
+            let reply = warp::reply();
+            Ok(warp::reply::with_header(reply, "Access-Control-Allow-Origin", "*").into_response())
+        }
+        Err(e) => Ok(handle_domain_errors(e)),
+    }
+}
+
+/// PUT /collection/smart/<collection-id>
+///
+/// Sets the rule of a smart collection, turning the collection into a smart collection if needed
+///
+/// inputs : deezer playlist id as a String, and Json with body
+/// {
+///     "rule": Rule // same as in /collection/smart
+/// }
+///
+/// outputs : empty
+pub fn set_smart_collection_rule() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("collection" / "smart" / String)
+        .and(warp::put())
+        .and(warp::body::json()) //JSON body
+        .and(warp::body::content_length_limit(1024 * 16)) // Avoids huge payloads
+        .and_then(call_set_smart_collection_rule)
+        .with(&get_cors_config())
+}
+
+async fn call_set_smart_collection_rule(
+    id: String,
+    set_smart_collection_rule_input: SetSmartCollectionRule,
+) -> Result<Response, Rejection> {
+    info!("setting rule of smart collection {}", id);
+    match set_smart_collection_rule_domain(id.as_str(), set_smart_collection_rule_input.rule) {
+        Ok(_) => {
+            let reply = warp::reply();
+            Ok(warp::reply::with_header(reply, "Access-Control-Allow-Origin", "*").into_response())
+        }
+        Err(e) => Ok(handle_domain_errors(e)),
+    }
+}
+
+/// PUT /collection/ordering/<collection-id>
+///
+/// Sets how the tracks inherited from the children are ordered in the collection when refreshed
+///
+/// inputs : deezer playlist id as a String, and Json with body
+/// {
+///     "track_order": String, // "grouped_by_child" (default), "time_add", "artist" or "title"
+///     "sort_whole_playlist": Option<bool> // sorts every track of the collection instead of only the added ones. false if not set, and not allowed with "grouped_by_child"
+/// }
+/// The refresh succeeds even if the tracks can not be ordered, an ordering_failed event being sent instead
+///
+/// outputs : empty
+pub fn set_collection_ordering() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("collection" / "ordering" / String)
+        .and(warp::put())
+        .and(warp::body::json()) //JSON body
+        .and(warp::body::content_length_limit(1024 * 16)) // Avoids huge payloads
+        .and_then(call_set_collection_ordering)
+        .with(&get_cors_config())
+}
+
+async fn call_set_collection_ordering(
+    id: String,
+    set_ordering_policy_input: SetOrderingPolicy,
+) -> Result<Response, Rejection> {
+    info!("setting ordering policy of collection {}", id);
+    let ordering = OrderingPolicy {
+        track_order: set_ordering_policy_input.track_order,
+        sort_whole_playlist: set_ordering_policy_input
+            .sort_whole_playlist
+            .unwrap_or(false),
+    };
+    match set_ordering_policy(id.as_str(), ordering) {
+        Ok(_) => {
+            let reply = warp::reply();
+            Ok(warp::reply::with_header(reply, "Access-Control-Allow-Origin", "*").into_response())
+        }
+        Err(e) => Ok(handle_domain_errors(e)),
+    }
+}
+
+/// PUT /collection/refresh-policy/<collection-id>
+///
+/// Sets when the collection is refreshed by the refresh of all the collections, and by the scheduler
+/// Only the fields that are set are updated
+///
+/// inputs : deezer playlist id as a String, and Json with body
+/// {
+///     "enabled": Option<bool>, // a disabled collection is only refreshed on demand. true for a new collection
+///     "schedule": Option<String> // "always" (default for a new collection), "daily", "weekly" or "monthly", at most once per calendar period unless the last refresh failed
+/// }
+///
+/// outputs : empty
+pub fn set_collection_refresh_policy(
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("collection" / "refresh-policy" / String)
+        .and(warp::put())
+        .and(warp::body::json()) //JSON body
+        .and(warp::body::content_length_limit(1024 * 16)) // Avoids huge payloads
+        .and_then(call_set_collection_refresh_policy)
+        .with(&get_cors_config())
+}
+
+async fn call_set_collection_refresh_policy(
+    id: String,
+    set_refresh_policy_input: SetRefreshPolicy,
+) -> Result<Response, Rejection> {
+    info!("setting refresh policy of collection {}", id);
+    match set_refresh_policy(
+        id.as_str(),
+        set_refresh_policy_input.enabled,
+        set_refresh_policy_input.schedule,
+    ) {
+        Ok(_) => {
+            let reply = warp::reply();
+            Ok(warp::reply::with_header(reply, "Access-Control-Allow-Origin", "*").into_response())
+        }
+        Err(e) => Ok(handle_domain_errors(e)),
+    }
+}
+
+/// GET /collection/list?tag=<tag>&search=<text>&sort=<sort>&direction=<direction>&limit=<limit>&offset=<offset>
+///
+/// Returns a list of the collections saved in the database, with their metadata
+/// If tag is set, only the collections having this tag are returned
+/// If search is set, only the collections whose name contains it are returned (case insensitive)
+/// The collections are sorted by sort ("name", "created_at" or "last_refreshed_at"), or in order of creation if not set
+/// The number of collections matching the query, in all the pages, is returned in the X-Total-Count header
+///
+/// inputs : optional query parameters
+///     - tag: String
+///     - search: String
+///     - sort: "name" | "created_at" | "last_refreshed_at"
+///     - direction: "asc" (default) | "desc"
+///     - limit: positive number, every collection is returned if not set
+///     - offset: number of collections to skip
 ///
 /// outputs : list of collections
+/// [{
+///     "name": String,
+///     "deezer_id": String,
+///     "url": String,
+///     "kind": String,
+///     "metadata": {
+///         "description": Option<String>,
+///         "tags": [String],
+///         "created_at": Option<u64>, // timestamp
+///         "last_refreshed_at": Option<u64>, // timestamp
+///         "last_refresh_status": Option<String>, // "success" or "failed"
+///         "last_refresh_error": Option<String>,
+///         "last_refresh_tracks_added": Option<u64>,
+///         "track_count": Option<u64> // number of tracks in deezer after the creation or the last successful refresh
+///     }
+/// }]
 pub fn get_collection_list() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
     warp::path!("collection" / "list")
         .and(warp::get()) // Avoids huge payloads
+        .and(warp::query::<ListCollectionsQuery>())
         .and_then(call_get_collection_list)
         .with(&get_cors_config())
 }
 
-async fn call_get_collection_list() -> Result<Response, Rejection> {
+async fn call_get_collection_list(query: ListCollectionsQuery) -> Result<Response, Rejection> {
     info!("getting collection list");
-    let collection_list = list_collections();
-    match collection_list {
-        Ok(collection_list) => {
-            let reply = warp::reply::json(&collection_list).into_response();
-            Ok(reply)
+    let collection_list_query = CollectionListQuery {
+        tag: query.tag,
+        search: query.search,
+        sort: query.sort,
+        direction: query.direction.unwrap_or_default(),
+        limit: query.limit,
+        offset: query.offset,
+    };
+    match list_collections_page(&collection_list_query) {
+        Ok(collection_page) => {
+            let reply = warp::reply::json(&collection_page.collections);
+            Ok(
+                warp::reply::with_header(reply, "X-Total-Count", collection_page.total_count)
+                    .into_response(),
+            )
+        }
+        Err(e) => Ok(handle_domain_errors(e)),
+    }
+}
+
+/// GET /collection/statistics/<collection-id>
+///
+/// Returns the statistics of a collection, computed from the deezer playlists of the collection and its children
+///
+/// inputs : deezer playlist id as a String
+///
+/// outputs : statistics of the collection
+/// {
+///     "deezer_id": String,
+///     "name": String,
+///     "track_count": Number,
+///     "own_track_count": Number, // tracks that are in none of the children
+///     "inherited_track_count": Number,
+///     "total_duration": Number, // in seconds
+///     "direct_children_count": Number,
+///     "transitive_children_count": Number,
+///     "direct_parents_count": Number,
+///     "transitive_parents_count": Number,
+///     "depth": Option<Number>, // distance from the nearest collection without parents, null if only reachable from a cycle
+///     "in_cycle": bool,
+///     "children": [{ // from the biggest share to the smallest
+///         "deezer_id": String,
+///         "name": String,
+///         "track_count": Number, // tracks of the collection that are also in the child
+///         "share": Number // of the tracks of the collection, between 0 and 1
+///     }]
+/// }
+pub fn get_collection_statistics() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("collection" / "statistics" / String)
+        .and(warp::get())
+        .and_then(call_get_collection_statistics)
+        .with(&get_cors_config())
+}
+
+async fn call_get_collection_statistics(collection_id: String) -> Result<Response, Rejection> {
+    info!("getting statistics of collection {}", collection_id);
+    match get_collection_statistics_domain(&collection_id).await {
+        Ok(statistics) => Ok(warp::reply::json(&statistics).into_response()),
+        Err(e) => Ok(handle_domain_errors(e)),
+    }
+}
+
+/// GET /collection-management/statistics
+///
+/// Returns the statistics of all the collections, computed from the database only
+///
+/// inputs : empty
+///
+/// outputs : global statistics
+/// {
+///     "collection_count": Number,
+///     "smart_collection_count": Number,
+///     "dependency_count": Number,
+///     "root_collection_count": Number, // collections without parents
+///     "leaf_collection_count": Number, // collections without children
+///     "collections_in_cycle_count": Number,
+///     "max_depth": Number,
+///     "track_count": Number // sum of the track counts saved at the last refresh of each collection
+/// }
+pub fn get_global_statistics() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("collection-management" / "statistics")
+        .and(warp::get())
+        .and_then(call_get_global_statistics)
+        .with(&get_cors_config())
+}
+
+async fn call_get_global_statistics() -> Result<Response, Rejection> {
+    info!("getting statistics of the collections");
+    match get_global_statistics_domain() {
+        Ok(statistics) => Ok(warp::reply::json(&statistics).into_response()),
+        Err(e) => Ok(handle_domain_errors(e)),
+    }
+}
+
+/// GET /collection-management/graph?format=<format>
+///
+/// Renders the collections and their dependencies, to be displayed with graphviz, mermaid or a graphml editor
+/// The collections are labelled with their name and track count, and the dependencies that are part of a cycle are highlighted
+///
+/// inputs : optional format query parameter, "dot" (default), "mermaid" or "graphml"
+///
+/// outputs : the graph as text
+pub fn get_collection_graph() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("collection-management" / "graph")
+        .and(warp::get())
+        .and(warp::query::<GraphQuery>())
+        .and_then(call_get_collection_graph)
+        .with(&get_cors_config())
+}
+
+async fn call_get_collection_graph(query: GraphQuery) -> Result<Response, Rejection> {
+    info!("exporting the collection graph");
+    let format = query.format.unwrap_or_default();
+    match export_collection_graph(&format) {
+        Ok(graph) => {
+            let content_type = match format {
+                GraphFormat::Dot => "text/vnd.graphviz; charset=utf-8",
+                GraphFormat::Mermaid => "text/plain; charset=utf-8",
+                GraphFormat::Graphml => "application/graphml+xml; charset=utf-8",
+            };
+            Ok(warp::reply::with_header(graph, "Content-Type", content_type).into_response())
+        }
+        Err(e) => Ok(handle_domain_errors(e)),
+    }
+}
+
+/// PUT /collection/metadata/<collection-id>
+///
+/// Sets the description and the tags of a collection. The fields that are not set are left unchanged
+///
+/// inputs : deezer playlist id as a String, and Json with body
+/// {
+///     "description": Option<String>, // an empty description removes it
+///     "tags": Option<[String]> // replaces every tag of the collection
+/// }
+///
+/// outputs : empty
+pub fn set_collection_metadata() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("collection" / "metadata" / String)
+        .and(warp::put())
+        .and(warp::body::json()) //JSON body
+        .and(warp::body::content_length_limit(1024 * 16)) // Avoids huge payloads
+        .and_then(call_set_collection_metadata)
+        .with(&get_cors_config())
+}
+
+async fn call_set_collection_metadata(
+    id: String,
+    set_collection_metadata_input: SetCollectionMetadata,
+) -> Result<Response, Rejection> {
+    info!("setting metadata of collection {}", id);
+    match set_collection_metadata_domain(
+        id.as_str(),
+        set_collection_metadata_input.description,
+        set_collection_metadata_input.tags,
+    ) {
+        Ok(_) => {
+            let reply = warp::reply();
+            Ok(warp::reply::with_header(reply, "Access-Control-Allow-Origin", "*").into_response())
         }
         Err(e) => Ok(handle_domain_errors(e)),
     }
@@ -119,6 +545,54 @@ async fn call_get_collection_tracks_excluding_children(id: String) -> Result<Res
     }
 }
 
+/// GET /collection/export/<collection-id>?format=<format>&view=<view>
+///
+/// Exports the tracklist of a collection, as a backup or to import it in another player
+/// Each track has its title, artist, deezer link and duration
+///
+/// inputs : deezer playlist id as a String
+/// optional format query parameter, "m3u" (default), "xspf", "jspf" or "csv"
+/// optional view query parameter, "flattened" (default) for all the tracks of the collection, "own" for the tracks that are not in its children
+///
+/// outputs : the tracklist as a file
+pub fn export_collection_tracklist() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone
+{
+    warp::path!("collection" / "export" / String)
+        .and(warp::get())
+        .and(warp::query::<TracklistExportQuery>())
+        .and_then(call_export_collection_tracklist)
+        .with(&get_cors_config())
+}
+
+async fn call_export_collection_tracklist(
+    id: String,
+    query: TracklistExportQuery,
+) -> Result<Response, Rejection> {
+    info!("exporting the tracklist of collection {}", id);
+    let format = query.format.unwrap_or_default();
+    match export_collection_tracklist_domain(&id, &format, &query.view.unwrap_or_default()).await {
+        Ok(tracklist) => {
+            let content_type = match format {
+                TracklistFormat::M3u => "audio/x-mpegurl; charset=utf-8",
+                TracklistFormat::Xspf => "application/xspf+xml; charset=utf-8",
+                TracklistFormat::Jspf => "application/jspf+json; charset=utf-8",
+                TracklistFormat::Csv => "text/csv; charset=utf-8",
+            };
+            let content_disposition = format!(
+                "attachment; filename=\"{}.{}\"",
+                id,
+                get_tracklist_file_extension(&format)
+            );
+            let reply = warp::reply::with_header(tracklist, "Content-Type", content_type);
+            Ok(
+                warp::reply::with_header(reply, "Content-Disposition", content_disposition)
+                    .into_response(),
+            )
+        }
+        Err(e) => Ok(handle_domain_errors(e)),
+    }
+}
+
 /// GET /collection/<collection_id>
 ///
 /// Returns the data of a collection by its deezer playlist id
@@ -126,6 +600,8 @@ async fn call_get_collection_tracks_excluding_children(id: String) -> Result<Res
 /// inputs : deezer playlist id as a String
 ///
 /// outputs : collection data
+/// "origin" is "created" if the deezer playlist was created by this app, "imported" otherwise
+/// "refresh_policy" is {"enabled": bool, "schedule": String}, see PUT /collection/refresh-policy
 pub fn get_collection_by_id() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
     warp::path!("collection" / String)
         .and(warp::get())
@@ -141,13 +617,37 @@ async fn call_get_collection_by_id(id: String) -> Result<Response, Rejection> {
     }
 }
 
+/// GET /collection/duplicates/<collection-id>
+///
+/// Returns the groups of tracks of a collection that are the same recording under different deezer ids
+/// (same isrc, or same title and artist ignoring case, punctuation, remaster and featuring mentions)
+///
+/// inputs : deezer playlist id as a String
+///
+/// outputs : list of duplicate groups
+/// [{ "tracks": [Track] }]
+pub fn get_collection_duplicates() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("collection" / "duplicates" / String)
+        .and(warp::get())
+        .and_then(call_get_collection_duplicates)
+        .with(&get_cors_config())
+}
+
+async fn call_get_collection_duplicates(id: String) -> Result<Response, Rejection> {
+    info!("getting duplicate tracks of collection {}", id);
+    match get_duplicate_groups(id.as_str()).await {
+        Ok(duplicate_groups) => Ok(warp::reply::json(&duplicate_groups).into_response()),
+        Err(e) => Ok(handle_domain_errors(e)),
+    }
+}
+
 /// GET /collection-management/children/<collection-id>
 ///
 /// Returns the children of a collection
 ///
 /// inputs : deezer playlist id as a String
 ///
-/// outputs : list of the children collections
+/// outputs : list of the children collections, with their filter if any
 pub fn get_direct_children_collections(
 ) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
     warp::path!("collection-management" / "children" / String)
@@ -167,11 +667,20 @@ async fn call_get_direct_children_collections(id: String) -> Result<Response, Re
 /// POST /collection-management/add-collection
 ///
 /// Adds a collection in another collection (child in parent)
+/// An optional filter restricts the tracks of the child that will be added to the parent
 ///
 /// inputs : Json with body
 /// {
 ///     "parent_collection_id": String, // parent deezer playlist id
-///     "child_collection_id": String // child deezer playlist id
+///     "child_collection_id": String, // child deezer playlist id
+///     "filter": Option<{ // every set condition has to match
+///         "artists_in": Option<[String]>, // deezer artist ids or names
+///         "artists_not_in": Option<[String]>, // deezer artist ids or names
+///         "exclude_explicit": Option<bool>,
+///         "min_duration": Option<u64>, // in seconds
+///         "max_duration": Option<u64>, // in seconds
+///         "title_regex": Option<String>
+///     }>
 /// }
 pub fn add_collection_to_parent() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
     warp::path!("collection-management" / "add-collection")
@@ -193,6 +702,7 @@ async fn call_add_collection_to_parent(
     match add_collection_dependency(
         add_collection_to_parent_input.parent_collection_id.as_str(),
         add_collection_to_parent_input.child_collection_id.as_str(),
+        add_collection_to_parent_input.filter,
     )
     .await
     {
@@ -204,21 +714,41 @@ async fn call_add_collection_to_parent(
     }
 }
 
-/// PUT /collection-management/refresh-collection/<collection-id>
+/// PUT /collection-management/refresh-collection/<collection-id>?dry_run=<bool>
 ///
 /// Refreshes a collection, i.e, adds the tracks from a child collection into the parent collection in deezer
+/// Tracks that are the same recording as a track already in the collection (same isrc, or same normalized title and artist) are skipped
+/// If dry_run is true, nothing is added to deezer and the tracks that would be added or skipped are returned instead
 ///
-/// inputs : deezer id as a String
+/// inputs : deezer id as a String, optional dry_run query parameter
 ///
-/// outputs : nothing
+/// outputs : nothing, or the refresh report if dry_run is true
+/// {
+///     "deezer_id": String,
+///     "tracks_to_add": [Track],
+///     "skipped_tracks": [{ "track": Track, "reason": { "type": "excluded", "exclusion": Exclusion } }]
+///     // reason can also be { "type": "filtered", "child_id": String }
+///     // or { "type": "duplicate", "duplicate_of": String, "matched_by": "isrc" | "title_and_artist" }
+/// }
 pub fn refresh_collection() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
     warp::path!("collection-management" / "refresh-collection" / String)
         .and(warp::put())
+        .and(warp::query::<RefreshCollectionQuery>())
         .and_then(call_refresh_collection)
         .with(&get_cors_config())
 }
 
-async fn call_refresh_collection(collection_id: String) -> Result<Response, Rejection> {
+async fn call_refresh_collection(
+    collection_id: String,
+    refresh_collection_query: RefreshCollectionQuery,
+) -> Result<Response, Rejection> {
+    if refresh_collection_query.dry_run.unwrap_or(false) {
+        info!("dry run refreshing collection {}", collection_id);
+        match refresh_collection_dry_run(collection_id.as_str()).await {
+            Ok(refresh_report) => return Ok(warp::reply::json(&refresh_report).into_response()),
+            Err(e) => return Ok(handle_domain_errors(e)),
+        }
+    }
     info!("refreshing collection {}", collection_id);
     match refresh_collection_domain(collection_id.as_str()).await {
         Ok(_) => {
@@ -254,44 +784,87 @@ async fn call_refresh_all_collections() -> Result<Response, Rejection> {
     }
 }
 
-/// DELETE /collection-management/remove-collection
+/// PUT /collection-management/watch-children
 ///
-/// Removes a collection from another collection (i.e removes a child collection from its parent)
-/// Warning : this will only affect the database, you will have to remove the associated tracks from deezer manually
+/// Runs one poll of the watcher : checks the checksum of the next child playlists, and refreshes the ancestors of the ones that changed
+/// The first checksum seen for a playlist is only recorded
 ///
-/// inputs : Json with body
+/// inputs : empty
+///
+/// outputs : Json with body
+/// [String] // deezer playlist ids of the refreshed collections
+pub fn watch_child_collections() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("collection-management" / "watch-children")
+        .and(warp::put())
+        .and_then(call_watch_child_collections)
+        .with(&get_cors_config())
+}
+
+async fn call_watch_child_collections() -> Result<Response, Rejection> {
+    info!("checking the child collections");
+    match watch_child_collections_domain().await {
+        Ok(refreshed_ids) => Ok(warp::reply::json(&refreshed_ids).into_response()),
+        Err(e) => Ok(handle_domain_errors(e)),
+    }
+}
+
+/// GET /collection-management/refresh-runs
+///
+/// Gets the last refreshes of all the collections started by the scheduler, the most recent first
+///
+/// inputs : empty
+///
+/// outputs : Json with body
+/// [{
+///     "id": Number,
+///     "started_at": Number, // timestamp
+///     "finished_at": Option<Number>, // timestamp, null while the refresh is in progress
+///     "status": "running" | "success" | "failed" | "skipped", // skipped if the previous refresh was still in progress
+///     "error": Option<String>
+/// }]
+pub fn get_refresh_runs() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("collection-management" / "refresh-runs")
+        .and(warp::get())
+        .and_then(call_get_refresh_runs)
+        .with(&get_cors_config())
+}
+
+async fn call_get_refresh_runs() -> Result<Response, Rejection> {
+    info!("getting the refresh runs");
+    match get_refresh_runs_domain() {
+        Ok(runs) => Ok(warp::reply::json(&runs).into_response()),
+        Err(e) => Ok(handle_domain_errors(e)),
+    }
+}
+
+/// PUT /collection/name/<collection-id>
+///
+/// Renames a collection, in deezer and in the database
+///
+/// inputs : deezer playlist id as a String, and Json with body
 /// {
-///     "parent_collection_id": String, // parent deezer playlist id
-///     "child_collection_id": String // child deezer playlist id
+///     "name": String
 /// }
 ///
 /// outputs : empty
-pub fn remove_collection_from_parent(
-) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
-    warp::path!("collection-management" / "remove-collection")
-        .and(warp::delete())
+pub fn rename_collection() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("collection" / "name" / String)
+        .and(warp::put())
         .and(warp::body::json()) //JSON body
         .and(warp::body::content_length_limit(1024 * 16)) // Avoids huge payloads
-        .and_then(call_remove_collection_to_parent)
+        .and_then(call_rename_collection)
         .with(&get_cors_config())
 }
 
-async fn call_remove_collection_to_parent(
-    remove_collection_to_parent_input: RemoveCollectionToParent,
+async fn call_rename_collection(
+    id: String,
+    rename_collection_input: RenameCollection,
 ) -> Result<Response, Rejection> {
     info!(
-        "removing collection {} from collection {}",
-        remove_collection_to_parent_input.child_collection_id,
-        remove_collection_to_parent_input.parent_collection_id
+        "renaming collection {} to {}",
+        id, rename_collection_input.name
     );
-    match remove_collection_dependency(
-        remove_collection_to_parent_input
-            .parent_collection_id
-            .as_str(),
-        remove_collection_to_parent_input
-            .child_collection_id
-            .as_str(),
-    ) {
+    match rename_collection_domain(id.as_str(), rename_collection_input.name.as_str()).await {
         Ok(_) => {
             let reply = warp::reply();
             Ok(warp::reply::with_header(reply, "Access-Control-Allow-Origin", "*").into_response())
@@ -300,24 +873,474 @@ async fn call_remove_collection_to_parent(
     }
 }
 
-/// DELETE /collection/<collection-id>
+/// PUT /collection-management/sync-collections
 ///
-/// Removes a collection
-/// Warning : this will only affect the database, you will have to remove the associated tracks from deezer manually
+/// Pulls the current titles and urls of the playlists from deezer, for all the collections
+/// The playlists that can not be read from deezer are skipped
 ///
-/// inputs : deezer playlist id as a String
+/// inputs : empty
 ///
-/// outputs : empty
+/// outputs : list of the collections that changed
+/// [{
+///     "deezer_id": String,
+///     "previous_name": String,
+///     "name": String,
+///     "previous_url": String,
+///     "url": String
+/// }]
+pub fn sync_collections_from_deezer() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone
+{
+    warp::path!("collection-management" / "sync-collections")
+        .and(warp::put())
+        .and_then(call_sync_collections_from_deezer)
+        .with(&get_cors_config())
+}
+
+async fn call_sync_collections_from_deezer() -> Result<Response, Rejection> {
+    info!("syncing collections from deezer");
+    match sync_collections_from_deezer_domain().await {
+        Ok(synced_collections) => Ok(warp::reply::json(&synced_collections).into_response()),
+        Err(e) => Ok(handle_domain_errors(e)),
+    }
+}
+
+/// GET /collection-management/user-playlists
+///
+/// Lists all the playlists of the deezer user
+///
+/// inputs : empty
+///
+/// outputs : list of the playlists of the user
+/// [{
+///     "deezer_id": String,
+///     "name": String,
+///     "url": String,
+///     "already_imported": bool // true if the playlist is already a collection
+/// }]
+pub fn get_user_playlists() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("collection-management" / "user-playlists")
+        .and(warp::get())
+        .and_then(call_get_user_playlists)
+        .with(&get_cors_config())
+}
+
+async fn call_get_user_playlists() -> Result<Response, Rejection> {
+    info!("getting the playlists of the user");
+    match get_user_playlists_domain().await {
+        Ok(user_playlists) => Ok(warp::reply::json(&user_playlists).into_response()),
+        Err(e) => Ok(handle_domain_errors(e)),
+    }
+}
+
+/// POST /collection-management/import-user-playlists
+///
+/// Registers the playlists of the deezer user as collections, in the database only
+///
+/// inputs : Json with body
+/// {
+///     "playlist_ids": Option<[String]> // deezer ids of the playlists to import. All the playlists of the user are imported if not set
+/// }
+///
+/// outputs : import report
+/// {
+///     "imported": [UserPlaylist], // playlists registered as new collections
+///     "already_existing": [UserPlaylist], // playlists that were already collections
+///     "not_found": [String], // selected ids that are not playlists of the user
+///     "failed": [{ "deezer_id": String, "error": String }]
+/// }
+pub fn import_user_playlists() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("collection-management" / "import-user-playlists")
+        .and(warp::post())
+        .and(warp::body::json()) //JSON body
+        .and(warp::body::content_length_limit(1024 * 16)) // Avoids huge payloads
+        .and_then(call_import_user_playlists)
+        .with(&get_cors_config())
+}
+
+async fn call_import_user_playlists(
+    import_user_playlists_input: ImportUserPlaylists,
+) -> Result<Response, Rejection> {
+    info!("importing the playlists of the user");
+    match import_user_playlists_domain(import_user_playlists_input.playlist_ids).await {
+        Ok(report) => Ok(warp::reply::json(&report).into_response()),
+        Err(e) => Ok(handle_domain_errors(e)),
+    }
+}
+
+/// POST /collection/import-file?format=<format>&name=<name>
+///
+/// Creates a new collection with the tracks of a playlist file exported by another player
+/// Each entry is matched with a deezer track by its deezer link, then its isrc, then a search by artist and title
+///
+/// inputs : the content of the file as body
+/// optional format query parameter, "m3u", "csv" or "xspf". Guessed from the content if not set
+/// optional name query parameter, defaults to the title of the playlist in the file
+/// the csv files need a header, with title, artist, isrc or link columns
+///
+/// outputs : import report
+/// {
+///     "deezer_id": String, // id of the created playlist
+///     "name": String,
+///     "matched": [{ "position": usize, "track_id": String, "matched_by": "link" | "isrc" | "search" }],
+///     "unmatched": [{ "position": usize, "title": Option<String>, "artist": Option<String>, "isrc": Option<String>, "location": Option<String> }]
+/// }
+pub fn import_playlist_file() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("collection" / "import-file")
+        .and(warp::post())
+        .and(warp::query::<ImportPlaylistFileQuery>())
+        .and(warp::body::content_length_limit(1024 * 1024)) // Avoids huge payloads
+        .and(warp::body::bytes())
+        .and_then(call_import_playlist_file)
+        .with(&get_cors_config())
+}
+
+async fn call_import_playlist_file(
+    query: ImportPlaylistFileQuery,
+    content: Bytes,
+) -> Result<Response, Rejection> {
+    info!("importing a playlist file");
+    let content = String::from_utf8_lossy(&content);
+    match import_playlist_file_domain(query.name, query.format, &content).await {
+        Ok(report) => Ok(warp::reply::json(&report).into_response()),
+        Err(e) => Ok(handle_domain_errors(e)),
+    }
+}
+
+/// POST /collection-management/hierarchy/plan?format=<format>&prune=<prune>
+///
+/// Lists the changes that would make the collections and their dependencies match a hierarchy file, without making them
+///
+/// inputs : the content of the hierarchy file as body, listing the collections by deezer id with their children
+/// e.g. in yaml : collections: [{ id: "123456", children: ["456789", "789012"] }, { id: "456789" }]
+/// optional format query parameter, "yaml" (default) or "toml"
+/// optional prune query parameter, to remove the collections that are not in the file (default false)
+/// the file should list at least one collection, and the dependencies it adds should not create a cycle
+///
+/// outputs : plan
+/// {
+///     "collections_to_add": [String], // created from their deezer playlist
+///     "collections_to_remove": [String], // collections that are not in the file, only if prune
+///     "dependencies_to_add": [{ "parent_id": String, "child_id": String }],
+///     "dependencies_to_remove": [{ "parent_id": String, "child_id": String }] // without the ones of the removed collections
+/// }
+pub fn plan_hierarchy() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("collection-management" / "hierarchy" / "plan")
+        .and(warp::post())
+        .and(warp::query::<HierarchyQuery>())
+        .and(warp::body::content_length_limit(1024 * 64)) // Avoids huge payloads
+        .and(warp::body::bytes())
+        .and_then(call_plan_hierarchy)
+        .with(&get_cors_config())
+}
+
+async fn call_plan_hierarchy(query: HierarchyQuery, content: Bytes) -> Result<Response, Rejection> {
+    info!("planning the hierarchy of the collections");
+    let content = String::from_utf8_lossy(&content);
+    match plan_hierarchy_domain(
+        &query.format.unwrap_or_default(),
+        &content,
+        query.prune.unwrap_or(false),
+    ) {
+        Ok(plan) => Ok(warp::reply::json(&plan).into_response()),
+        Err(e) => Ok(handle_domain_errors(e)),
+    }
+}
+
+/// POST /collection-management/hierarchy/apply?format=<format>&prune=<prune>
+///
+/// Makes the collections and their dependencies match a hierarchy file
+/// The missing collections are created from their deezer playlist, and with prune the collections that are not in the file are removed
+/// Nothing is changed if the plan is invalid
+///
+/// inputs : same as /collection-management/hierarchy/plan
+///
+/// outputs : the applied plan, same as /collection-management/hierarchy/plan
+pub fn apply_hierarchy() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("collection-management" / "hierarchy" / "apply")
+        .and(warp::post())
+        .and(warp::query::<HierarchyQuery>())
+        .and(warp::body::content_length_limit(1024 * 64)) // Avoids huge payloads
+        .and(warp::body::bytes())
+        .and_then(call_apply_hierarchy)
+        .with(&get_cors_config())
+}
+
+async fn call_apply_hierarchy(
+    query: HierarchyQuery,
+    content: Bytes,
+) -> Result<Response, Rejection> {
+    info!("applying the hierarchy of the collections");
+    let content = String::from_utf8_lossy(&content);
+    match apply_hierarchy_domain(
+        &query.format.unwrap_or_default(),
+        &content,
+        query.prune.unwrap_or(false),
+    )
+    .await
+    {
+        Ok(plan) => Ok(warp::reply::json(&plan).into_response()),
+        Err(e) => Ok(handle_domain_errors(e)),
+    }
+}
+
+/// POST /collection-management/dependencies/batch
+///
+/// Adds and removes dependencies between existing collections, in order
+/// Every operation is validated against the state left by the previous ones, and they are all applied in one transaction, or none of them if one is invalid
+///
+/// inputs : Json with body
+/// {
+///     "operations": [{
+///         "action": "add" | "remove",
+///         "parent_collection_id": String,
+///         "child_collection_id": String,
+///         "filter": Option<TrackFilter> // only for the additions, same as /collection-management/add-collection
+///     }],
+///     "allow_cycles": Option<bool> // the additions creating a cycle are invalid if not set
+/// }
+///
+/// outputs : batch report, with a 400 status if an operation is invalid
+/// {
+///     "applied": bool,
+///     "results": [{
+///         "index": usize, // position of the operation in the batch
+///         "status": "applied" | "invalid" | "not_applied", // not_applied if valid but another operation is invalid
+///         "error": Option<String>
+///     }]
+/// }
+pub fn apply_dependency_batch() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("collection-management" / "dependencies" / "batch")
+        .and(warp::post())
+        .and(warp::body::json()) //JSON body
+        .and(warp::body::content_length_limit(1024 * 64)) // Avoids huge payloads
+        .and_then(call_apply_dependency_batch)
+        .with(&get_cors_config())
+}
+
+async fn call_apply_dependency_batch(
+    dependency_batch_input: DependencyBatchInput,
+) -> Result<Response, Rejection> {
+    info!(
+        "applying a batch of {} dependency operations",
+        dependency_batch_input.operations.len()
+    );
+    let operations = dependency_batch_input
+        .operations
+        .into_iter()
+        .map(|operation| DependencyOperation {
+            action: operation.action,
+            parent_id: operation.parent_collection_id,
+            child_id: operation.child_collection_id,
+            filter: operation.filter,
+        })
+        .collect::<Vec<_>>();
+    match apply_dependency_batch_domain(
+        operations,
+        dependency_batch_input.allow_cycles.unwrap_or(false),
+    ) {
+        Ok(report) => {
+            let status = if report.applied {
+                warp::http::StatusCode::OK
+            } else {
+                warp::http::StatusCode::BAD_REQUEST
+            };
+            Ok(warp::reply::with_status(warp::reply::json(&report), status).into_response())
+        }
+        Err(e) => Ok(handle_domain_errors(e)),
+    }
+}
+
+/// GET /collection-management/health
+///
+/// Checks every collection against deezer, and every dependency against the collections
+///
+/// inputs : empty
+///
+/// outputs : health report
+/// {
+///     "collections": [{
+///         "deezer_id": String,
+///         "name": String,
+///         "issue": {"type": "deleted" | "unreadable" | "private" | "owner_changed", ...}
+///     }],
+///     "dangling_dependencies": [{
+///         "dependency_id": Number,
+///         "parent_id": Option<String>, // null if the parent collection does not exist anymore
+///         "child_id": Option<String> // null if the child collection does not exist anymore
+///     }],
+///     "actions": [] // always empty
+/// }
+pub fn get_health_report() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("collection-management" / "health")
+        .and(warp::get())
+        .and_then(call_get_health_report)
+        .with(&get_cors_config())
+}
+
+async fn call_get_health_report() -> Result<Response, Rejection> {
+    info!("checking the health of the collections");
+    match check_collections_health(false, false).await {
+        Ok(report) => Ok(warp::reply::json(&report).into_response()),
+        Err(e) => Ok(handle_domain_errors(e)),
+    }
+}
+
+/// PUT /collection-management/health
+///
+/// Checks the health of the collections, then fixes the issues found
+///     - prune : removes the collections whose playlist was deleted from deezer, and the dangling dependencies
+///     - repair : saves the current owner of the playlists. A created playlist that changed owner is considered imported
+///
+/// inputs : optional prune and repair query parameters
+///
+/// outputs : health report, with the actions done
+/// {
+///     "collections": [...],
+///     "dangling_dependencies": [...],
+///     "actions": [{"type": "collection_pruned" | "dependency_pruned" | "owner_updated", ...}]
+/// }
+pub fn fix_health_issues() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("collection-management" / "health")
+        .and(warp::put())
+        .and(warp::query::<HealthQuery>())
+        .and_then(call_fix_health_issues)
+        .with(&get_cors_config())
+}
+
+async fn call_fix_health_issues(query: HealthQuery) -> Result<Response, Rejection> {
+    info!("fixing the health issues of the collections");
+    match check_collections_health(query.prune.unwrap_or(false), query.repair.unwrap_or(false))
+        .await
+    {
+        Ok(report) => Ok(warp::reply::json(&report).into_response()),
+        Err(e) => Ok(handle_domain_errors(e)),
+    }
+}
+
+/// POST /collection-management/move-collection
+///
+/// Moves a collection from a parent to another one in one transaction, keeping the filter of the dependency
+/// Warning : this will only affect the database, the tracks inherited from the child stay in the old parent on deezer
+///
+/// inputs : Json with body
+/// {
+///     "child_collection_id": String, // deezer playlist id of the moved collection
+///     "old_parent_collection_id": String,
+///     "new_parent_collection_id": String,
+///     "refresh": Option<bool> // refreshes the new parent once moved. The move is kept if the refresh fails
+/// }
+///
+/// outputs : Json with body
+/// {
+///     "moved": bool,
+///     "refresh_error": Option<String> // why the refresh of the new parent failed, if it did
+/// }
+pub fn move_collection() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("collection-management" / "move-collection")
+        .and(warp::post())
+        .and(warp::body::json()) //JSON body
+        .and(warp::body::content_length_limit(1024 * 16)) // Avoids huge payloads
+        .and_then(call_move_collection)
+        .with(&get_cors_config())
+}
+
+async fn call_move_collection(
+    move_collection_input: MoveCollection,
+) -> Result<Response, Rejection> {
+    info!(
+        "moving collection {} from collection {} to collection {}",
+        move_collection_input.child_collection_id,
+        move_collection_input.old_parent_collection_id,
+        move_collection_input.new_parent_collection_id
+    );
+    match move_collection_domain(
+        &move_collection_input.child_collection_id,
+        &move_collection_input.old_parent_collection_id,
+        &move_collection_input.new_parent_collection_id,
+        move_collection_input.refresh.unwrap_or(false),
+    )
+    .await
+    {
+        Ok(report) => Ok(warp::reply::json(&report).into_response()),
+        Err(e) => Ok(handle_domain_errors(e)),
+    }
+}
+
+/// DELETE /collection-management/remove-collection
+///
+/// Removes a collection from another collection (i.e removes a child collection from its parent)
+/// Warning : this will only affect the database, you will have to remove the associated tracks from deezer manually
+///
+/// inputs : Json with body
+/// {
+///     "parent_collection_id": String, // parent deezer playlist id
+///     "child_collection_id": String // child deezer playlist id
+/// }
+///
+/// outputs : empty
+pub fn remove_collection_from_parent(
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("collection-management" / "remove-collection")
+        .and(warp::delete())
+        .and(warp::body::json()) //JSON body
+        .and(warp::body::content_length_limit(1024 * 16)) // Avoids huge payloads
+        .and_then(call_remove_collection_to_parent)
+        .with(&get_cors_config())
+}
+
+async fn call_remove_collection_to_parent(
+    remove_collection_to_parent_input: RemoveCollectionToParent,
+) -> Result<Response, Rejection> {
+    info!(
+        "removing collection {} from collection {}",
+        remove_collection_to_parent_input.child_collection_id,
+        remove_collection_to_parent_input.parent_collection_id
+    );
+    match remove_collection_dependency(
+        remove_collection_to_parent_input
+            .parent_collection_id
+            .as_str(),
+        remove_collection_to_parent_input
+            .child_collection_id
+            .as_str(),
+    ) {
+        Ok(_) => {
+            let reply = warp::reply();
+            Ok(warp::reply::with_header(reply, "Access-Control-Allow-Origin", "*").into_response())
+        }
+        Err(e) => Ok(handle_domain_errors(e)),
+    }
+}
+
+/// DELETE /collection/<collection-id>?delete_playlist=<bool>
+///
+/// Removes a collection
+/// Warning : unless delete_playlist is true, this will only affect the database, you will have to remove the associated tracks from deezer manually
+/// If delete_playlist is true, the deezer playlist is also deleted. This is refused (403) for the playlists that were not created by this app,
+/// i.e imported from an existing playlist, or that do not belong to the current user anymore
+///
+/// inputs : deezer playlist id as a String, optional delete_playlist query parameter
+///
+/// outputs : empty
 pub fn remove_collection() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
     warp::path!("collection" / String)
         .and(warp::delete())
+        .and(warp::query::<RemoveCollectionQuery>())
         .and_then(call_remove_collection)
         .with(&get_cors_config())
 }
 
-async fn call_remove_collection(collection_id: String) -> Result<Response, Rejection> {
+async fn call_remove_collection(
+    collection_id: String,
+    query: RemoveCollectionQuery,
+) -> Result<Response, Rejection> {
     info!("removing collection {}", collection_id);
-    match remove_collection_domain(collection_id.as_str()) {
+    let result = if query.delete_playlist.unwrap_or(false) {
+        remove_collection_and_playlist(collection_id.as_str()).await
+    } else {
+        remove_collection_domain(collection_id.as_str())
+    };
+    match result {
         Ok(_) => {
             let reply = warp::reply();
             Ok(warp::reply::with_header(reply, "Access-Control-Allow-Origin", "*").into_response())
@@ -353,6 +1376,220 @@ async fn call_clear_data() -> Result<Response, Rejection> {
     }
 }
 
+/// GET /events
+///
+/// Streams the collection events as server-sent events, as they happen :
+///     - collection_created, collection_renamed (with the new name), collection_removed
+///     - dependency_added, dependency_removed
+///     - refresh_started, collection_refreshed (with the added track ids), refresh_failed
+///     - ordering_failed, when the tracks added by a refresh could not be ordered
+///
+/// inputs :
+///     - since : optional query param, replays first the last events published after this event id
+///     - follow : optional query param, false to end the stream after the replayed events (default true)
+///
+/// outputs : event stream, each event having an increasing id and its data being a Json with a "type" field matching the event name
+pub fn get_events() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("events")
+        .and(warp::get())
+        .and(warp::query::<EventsQuery>())
+        .map(call_get_events)
+        .with(&get_cors_config())
+}
+
+fn call_get_events(query: EventsQuery) -> impl Reply {
+    info!("subscribing to the collection events");
+    let (past_events, receiver) = subscribe_events(query.since);
+    let live_events = match query.follow.unwrap_or(true) {
+        true => Either::Left(BroadcastStream::new(receiver).filter_map(|event| match event {
+            Ok(event) => Some(event),
+            Err(e) => {
+                warn!("Events subscriber lagging behind : {:?}", e);
+                None
+            }
+        })),
+        false => Either::Right(tokio_stream::empty()),
+    };
+    let events = tokio_stream::iter(past_events)
+        .chain(live_events)
+        .filter_map(convert_event_to_sse);
+    return warp::sse::reply(warp::sse::keep_alive().stream(events));
+}
+
+fn convert_event_to_sse(published_event: PublishedEvent) -> Option<Result<Event, Infallible>> {
+    match Event::default()
+        .id(published_event.id.to_string())
+        .event(published_event.event.name())
+        .json_data(&published_event.event)
+    {
+        Ok(sse_event) => return Some(Ok(sse_event)),
+        Err(e) => {
+            warn!(
+                "Error while serializing event {:?} : {:?}",
+                published_event, e
+            );
+            return None;
+        }
+    }
+}
+
+/// GET /collection-management/exclusions/<collection-id>
+///
+/// Returns the exclusions of a collection, i.e the tracks and artists that will never be added from its children
+///
+/// inputs : deezer playlist id as a String
+///
+/// outputs : list of exclusions
+/// [{
+///     "kind": "track" | "artist",
+///     "value": String // deezer track id, or deezer artist id or name
+/// }]
+pub fn get_collection_exclusions() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("collection-management" / "exclusions" / String)
+        .and(warp::get())
+        .and_then(call_get_collection_exclusions)
+        .with(&get_cors_config())
+}
+
+async fn call_get_collection_exclusions(id: String) -> Result<Response, Rejection> {
+    info!("getting exclusions of collection {}", id);
+    match get_exclusions(id.as_str()) {
+        Ok(exclusions) => Ok(warp::reply::json(&exclusions).into_response()),
+        Err(e) => Ok(handle_domain_errors(e)),
+    }
+}
+
+/// POST /collection-management/exclusions/<collection-id>
+///
+/// Adds an exclusion to a collection, the excluded track or artist will be skipped when refreshing the collection
+///
+/// inputs : deezer playlist id as a String, and Json with body
+/// {
+///     "kind": "track" | "artist",
+///     "value": String // deezer track id, or deezer artist id or name
+/// }
+///
+/// outputs : empty
+pub fn add_collection_exclusion() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("collection-management" / "exclusions" / String)
+        .and(warp::post())
+        .and(warp::body::json()) //JSON body
+        .and(warp::body::content_length_limit(1024 * 16)) // Avoids huge payloads
+        .and_then(call_add_collection_exclusion)
+        .with(&get_cors_config())
+}
+
+async fn call_add_collection_exclusion(
+    id: String,
+    exclusion_input: ExclusionInput,
+) -> Result<Response, Rejection> {
+    info!(
+        "adding exclusion {:?} {} to collection {}",
+        exclusion_input.kind, exclusion_input.value, id
+    );
+    let exclusion = Exclusion {
+        kind: exclusion_input.kind,
+        value: exclusion_input.value,
+    };
+    match add_exclusion(id.as_str(), exclusion) {
+        Ok(_) => {
+            let reply = warp::reply();
+            Ok(warp::reply::with_header(reply, "Access-Control-Allow-Origin", "*").into_response())
+        }
+        Err(e) => Ok(handle_domain_errors(e)),
+    }
+}
+
+/// DELETE /collection-management/exclusions/<collection-id>
+///
+/// Removes an exclusion from a collection
+///
+/// inputs : deezer playlist id as a String, and Json with body
+/// {
+///     "kind": "track" | "artist",
+///     "value": String // deezer track id, or deezer artist id or name
+/// }
+///
+/// outputs : empty
+pub fn remove_collection_exclusion() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone
+{
+    warp::path!("collection-management" / "exclusions" / String)
+        .and(warp::delete())
+        .and(warp::body::json()) //JSON body
+        .and(warp::body::content_length_limit(1024 * 16)) // Avoids huge payloads
+        .and_then(call_remove_collection_exclusion)
+        .with(&get_cors_config())
+}
+
+async fn call_remove_collection_exclusion(
+    id: String,
+    exclusion_input: ExclusionInput,
+) -> Result<Response, Rejection> {
+    info!(
+        "removing exclusion {:?} {} from collection {}",
+        exclusion_input.kind, exclusion_input.value, id
+    );
+    let exclusion = Exclusion {
+        kind: exclusion_input.kind,
+        value: exclusion_input.value,
+    };
+    match remove_exclusion(id.as_str(), exclusion) {
+        Ok(_) => {
+            let reply = warp::reply();
+            Ok(warp::reply::with_header(reply, "Access-Control-Allow-Origin", "*").into_response())
+        }
+        Err(e) => Ok(handle_domain_errors(e)),
+    }
+}
+
+/// PUT /collection-management/dependency-filter
+///
+/// Sets the filter applied on the tracks of a child collection when refreshing its parent
+/// The filter is removed if it is not set
+///
+/// inputs : Json with body
+/// {
+///     "parent_collection_id": String, // parent deezer playlist id
+///     "child_collection_id": String, // child deezer playlist id
+///     "filter": Option<Filter> // same as in /collection-management/add-collection
+/// }
+///
+/// outputs : empty
+pub fn set_collection_dependency_filter(
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("collection-management" / "dependency-filter")
+        .and(warp::put())
+        .and(warp::body::json()) //JSON body
+        .and(warp::body::content_length_limit(1024 * 16)) // Avoids huge payloads
+        .and_then(call_set_collection_dependency_filter)
+        .with(&get_cors_config())
+}
+
+async fn call_set_collection_dependency_filter(
+    set_collection_dependency_filter_input: SetCollectionDependencyFilter,
+) -> Result<Response, Rejection> {
+    info!(
+        "setting filter of collection {} in {}",
+        set_collection_dependency_filter_input.child_collection_id,
+        set_collection_dependency_filter_input.parent_collection_id
+    );
+    match set_collection_dependency_filter_domain(
+        set_collection_dependency_filter_input
+            .parent_collection_id
+            .as_str(),
+        set_collection_dependency_filter_input
+            .child_collection_id
+            .as_str(),
+        set_collection_dependency_filter_input.filter,
+    ) {
+        Ok(_) => {
+            let reply = warp::reply();
+            Ok(warp::reply::with_header(reply, "Access-Control-Allow-Origin", "*").into_response())
+        }
+        Err(e) => Ok(handle_domain_errors(e)),
+    }
+}
+
 fn get_cors_config() -> Builder {
     return warp::cors()
         .allow_any_origin()
@@ -363,17 +1600,22 @@ fn get_cors_config() -> Builder {
             "X-Requested-With",
             "Content-Type",
         ])
-        .allow_methods(vec!["GET", "POST", "PUT", "DELETE"]);
+        .allow_methods(vec!["GET", "POST", "PUT", "DELETE"])
+        .expose_headers(vec!["X-Total-Count"]);
 }
 
 fn handle_domain_errors(e: DomainError) -> Response {
     match e {
-        crate::domain::errors::DomainError::DomainParamError() => {
+        crate::domain::errors::DomainError::DomainParamError(_) => {
             return warp::reply::with_status("BAD_REQUEST", warp::http::StatusCode::BAD_REQUEST)
                 .into_response();
         }
-        crate::domain::errors::DomainError::DomainDataError()
-        | crate::domain::errors::DomainError::DomainMusicServiceError() => {
+        crate::domain::errors::DomainError::DomainForbiddenError(_) => {
+            return warp::reply::with_status("FORBIDDEN", warp::http::StatusCode::FORBIDDEN)
+                .into_response();
+        }
+        crate::domain::errors::DomainError::DomainDataError(_)
+        | crate::domain::errors::DomainError::DomainMusicServiceError(_) => {
             return warp::reply::with_status(
                 "INTERNAL_SERVER_ERROR",
                 warp::http::StatusCode::INTERNAL_SERVER_ERROR,
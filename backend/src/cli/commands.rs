@@ -0,0 +1,132 @@
+use crate::{
+    common::common::INTEGRATION_TESTS_ARG,
+    domain::{
+        collection_health::check_collections_health,
+        collection_hierarchy::{apply_hierarchy, plan_hierarchy},
+        domain_models::{GraphFormat, HierarchyFormat},
+        graph_export::export_collection_graph,
+    },
+};
+
+const USAGE: &str = "usage :
+    backend                              starts the server
+    backend health [--prune] [--repair]  checks the health of the collections, and optionally fixes the issues
+    backend graph [--format <format>]    prints the graph of the collections, as dot (default), mermaid or graphml
+    backend plan <file> [--prune]        prints the changes needed to match the yaml or toml hierarchy file
+    backend apply <file> [--prune]       makes the collections and their dependencies match the yaml or toml hierarchy file,
+                                         and removes the collections missing from the file with --prune";
+
+// returns the command line arguments, without the program name and the integration tests marker
+pub fn get_command_args(args: Vec<String>) -> Vec<String> {
+    return args
+        .into_iter()
+        .skip(1)
+        .filter(|arg| arg != INTEGRATION_TESTS_ARG)
+        .collect::<Vec<_>>();
+}
+
+// runs the command and returns the exit code of the process
+pub async fn run_command(args: &[String]) -> i32 {
+    let (command, options) = match args.split_first() {
+        Some((command, options)) => (command.as_str(), options),
+        None => return print_usage(),
+    };
+    match command {
+        "health" => return run_health(options).await,
+        "graph" => return run_graph(options),
+        "plan" => return run_hierarchy(options, false).await,
+        "apply" => return run_hierarchy(options, true).await,
+        _ => return print_usage(),
+    }
+}
+
+async fn run_health(options: &[String]) -> i32 {
+    let mut prune = false;
+    let mut repair = false;
+    for option in options.iter() {
+        match option.as_str() {
+            "--prune" => prune = true,
+            "--repair" => repair = true,
+            _ => return print_usage(),
+        }
+    }
+    match check_collections_health(prune, repair).await {
+        Ok(report) => {
+            println!("{}", serde_json::to_string_pretty(&report).unwrap());
+            return 0;
+        }
+        Err(e) => {
+            eprintln!(
+                "Error while checking the health of the collections : {:?}",
+                e
+            );
+            return 1;
+        }
+    }
+}
+
+fn run_graph(options: &[String]) -> i32 {
+    let format = match options {
+        [] => GraphFormat::Dot,
+        [option, format] if option == "--format" => match format.as_str() {
+            "dot" => GraphFormat::Dot,
+            "mermaid" => GraphFormat::Mermaid,
+            "graphml" => GraphFormat::Graphml,
+            _ => return print_usage(),
+        },
+        _ => return print_usage(),
+    };
+    match export_collection_graph(&format) {
+        Ok(graph) => {
+            print!("{}", graph);
+            return 0;
+        }
+        Err(e) => {
+            eprintln!("Error while exporting the collection graph : {:?}", e);
+            return 1;
+        }
+    }
+}
+
+async fn run_hierarchy(options: &[String], apply: bool) -> i32 {
+    let (path, prune) = match options {
+        [path] => (path, false),
+        [path, option] if option == "--prune" => (path, true),
+        _ => return print_usage(),
+    };
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) => {
+            eprintln!("Error while reading the hierarchy file {} : {}", path, e);
+            return 1;
+        }
+    };
+    let format = if path.ends_with(".toml") {
+        HierarchyFormat::Toml
+    } else {
+        HierarchyFormat::Yaml
+    };
+    let plan = if apply {
+        apply_hierarchy(&format, &content, prune).await
+    } else {
+        plan_hierarchy(&format, &content, prune)
+    };
+    match plan {
+        Ok(plan) => {
+            println!("{}", serde_json::to_string_pretty(&plan).unwrap());
+            return 0;
+        }
+        Err(e) => {
+            eprintln!(
+                "Error while reconciling the hierarchy file {} : {:?}",
+                path, e
+            );
+            return 1;
+        }
+    }
+}
+
+fn print_usage() -> i32 {
+    eprintln!("{}", USAGE);
+    return 2;
+}
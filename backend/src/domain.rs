@@ -1,7 +1,29 @@
 pub mod collection_commons;
 pub mod collection_dependencies;
+pub mod collection_exclusions;
+pub mod collection_graph;
+pub mod collection_health;
+pub mod collection_hierarchy;
+pub mod collection_import;
 pub mod collection_management;
+pub mod collection_metadata;
+pub mod collection_refresh_policy;
+pub mod collection_statistics;
+pub mod collection_sync;
+pub mod collection_watcher;
 pub mod controllers;
 pub mod converter;
+pub mod deezer_links;
+pub mod dependency_batch;
 pub mod domain_models;
+pub mod duplicates;
 pub mod errors;
+pub mod events;
+pub mod graph_export;
+pub mod playlist_file_import;
+pub mod playlist_files;
+pub mod refresh_scheduler;
+pub mod smart_collections;
+pub mod track_filters;
+pub mod track_ordering;
+pub mod tracklist_export;
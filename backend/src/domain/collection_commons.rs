@@ -1,22 +1,42 @@
-use super::converter::convert_playlist;
-use super::domain_models::Playlist;
+use super::converter::{convert_collection_origin_to_string, convert_playlist};
+use super::deezer_links::get_canonical_playlist_url;
+use super::domain_models::{CollectionOrigin, Playlist};
 use super::errors::DomainError;
+use super::events::{publish_event, CollectionEvent};
+use crate::common::common::get_env_variable;
 use crate::infrastructure;
 use crate::infrastructure::database::get_collection_id_by_deezer_id as get_collection_id_by_deezer_id_database;
 use crate::infrastructure::database_models::InitCollectionDatabase;
 use crate::infrastructure::deezer::create_playlist;
+use futures::{stream, StreamExt, TryStreamExt};
 use log::error;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-pub async fn create_new_playlist(name: &str) -> Result<bool, DomainError> {
-    match create_playlist(name).await {
+// Number of playlists fetched at the same time when MAX_CONCURRENT_REQUESTS is not set
+const DEFAULT_MAX_CONCURRENT_REQUESTS: usize = 4;
+
+// creates a new deezer playlist and its collection, and returns the created playlist id
+pub async fn create_new_playlist(name: &str) -> Result<u64, DomainError> {
+    let id = match create_playlist(name).await {
         Ok(id) => {
             let database_collection = InitCollectionDatabase {
                 name: name.to_string(),
-                url: format!("https://www.deezer.com/fr/playlist/{}", id),
+                url: get_canonical_playlist_url(&id),
                 deezer_id: format!("{}", id),
+                created_at: get_current_timestamp() as i64,
+                track_count: 0,
+                origin: convert_collection_origin_to_string(&CollectionOrigin::Created).to_string(),
+                owner_id: Some(get_env_variable("DEEZER_USER_ID")),
             };
             match infrastructure::database::init_collection(database_collection) {
-                Ok(_) => {}
+                Ok(inserted) => {
+                    if inserted > 0 {
+                        publish_event(CollectionEvent::CollectionCreated {
+                            deezer_id: id.to_string(),
+                            name: name.to_string(),
+                        });
+                    }
+                }
                 Err(e) => {
                     return Err(log_database_error(&format!(
                         "Error while initializing a collection {} in the database : {:?}",
@@ -24,6 +44,7 @@ pub async fn create_new_playlist(name: &str) -> Result<bool, DomainError> {
                     )));
                 }
             };
+            id
         }
         Err(e) => {
             return Err(log_deezer_error(&format!(
@@ -31,8 +52,8 @@ pub async fn create_new_playlist(name: &str) -> Result<bool, DomainError> {
                 name, e
             )));
         }
-    }
-    return Ok(true);
+    };
+    return Ok(id);
 }
 
 pub async fn create_collection_from_playlist(id: &u64) -> Result<bool, DomainError> {
@@ -62,9 +83,23 @@ pub async fn get_playlist(id: &u64) -> Result<Playlist, DomainError> {
     }
 }
 
-pub fn get_playlist_id_from_url(url: String) -> u64 {
-    let id_str: Vec<&str> = url.split('/').collect();
-    return convert_string_to_u64(id_str.last().unwrap());
+// fetches the playlists concurrently, at most MAX_CONCURRENT_REQUESTS at a time, and returns them in the order of the ids.
+// The requests still go through the rate limiter
+pub async fn get_playlists(ids: &[String]) -> Result<Vec<Playlist>, DomainError> {
+    // the ids are owned by the futures, for the handlers to stay Send
+    return stream::iter(ids.to_vec())
+        .map(|id| async move { get_playlist(&convert_string_to_u64(&id)).await })
+        .buffered(get_max_concurrent_requests())
+        .try_collect::<Vec<_>>()
+        .await;
+}
+
+pub fn get_max_concurrent_requests() -> usize {
+    let max_concurrent_requests = get_env_variable("MAX_CONCURRENT_REQUESTS");
+    if max_concurrent_requests.is_empty() {
+        return DEFAULT_MAX_CONCURRENT_REQUESTS;
+    }
+    return (convert_string_to_u64(&max_concurrent_requests) as usize).max(1);
 }
 
 pub fn convert_string_to_u64(id: &str) -> u64 {
@@ -80,17 +115,6 @@ pub fn convert_string_to_u64(id: &str) -> u64 {
     }
 }
 
-pub fn get_track_id_from_url(url: String) -> u64 {
-    let id_str: Vec<&str> = url.split('/').collect();
-    match id_str.last().unwrap().parse::<u64>() {
-        Ok(id) => return id,
-        Err(e) => {
-            error!("Handler : Error while parsing url {} : {:?}", url, e);
-            return 0;
-        }
-    }
-}
-
 pub fn get_collection_id_by_deezer_id(id: &str) -> Result<i32, DomainError> {
     match get_collection_id_by_deezer_id_database(id) {
         Ok(id) => {
@@ -105,32 +129,65 @@ pub fn get_collection_id_by_deezer_id(id: &str) -> Result<i32, DomainError> {
     };
 }
 
+pub fn get_current_timestamp() -> u64 {
+    return SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+}
+
+// escapes the xml special characters, for the exported xml documents
+pub fn escape_xml(value: &str) -> String {
+    return value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;");
+}
+
 pub fn log_parameters_error(failures: String) -> Result<bool, DomainError> {
     if !failures.is_empty() {
         error!("Handler - error in input parameters : {}", failures);
-        return Err(DomainError::DomainParamError());
+        return Err(DomainError::DomainParamError(failures));
     }
     return Ok(true);
 }
 
 pub fn log_database_error(message: &str) -> DomainError {
     error!("Handler : {}", message);
-    return DomainError::DomainDataError();
+    return DomainError::DomainDataError(message.to_string());
 }
 
 pub fn log_deezer_error(message: &str) -> DomainError {
     error!("Handler : {}", message);
-    return DomainError::DomainMusicServiceError();
+    return DomainError::DomainMusicServiceError(message.to_string());
+}
+
+pub fn log_forbidden_error(message: &str) -> DomainError {
+    error!("Handler : {}", message);
+    return DomainError::DomainForbiddenError(message.to_string());
 }
 
 fn add_playlist_data_to_database(playlist: Playlist) -> Result<bool, DomainError> {
     let database_collection = InitCollectionDatabase {
-        name: playlist.title,
-        url: playlist.url.clone(),
+        name: playlist.title.clone(),
+        url: get_canonical_playlist_url(&playlist.id),
         deezer_id: playlist.id.to_string(),
+        created_at: get_current_timestamp() as i64,
+        track_count: playlist.tracks.len() as i32,
+        origin: convert_collection_origin_to_string(&CollectionOrigin::Imported).to_string(),
+        owner_id: Some(playlist.creator_id.to_string()),
     };
     match infrastructure::database::init_collection(database_collection) {
-        Ok(_) => {}
+        Ok(inserted) => {
+            if inserted > 0 {
+                publish_event(CollectionEvent::CollectionCreated {
+                    deezer_id: playlist.id.to_string(),
+                    name: playlist.title,
+                });
+            }
+        }
         Err(e) => {
             return Err(log_database_error(&format!(
                 "Error while initializing the collection {} in the database : {:?}",
@@ -3,7 +3,10 @@ use super::{
         convert_string_to_u64, create_collection_from_playlist, get_collection_id_by_deezer_id,
         log_database_error, log_deezer_error, log_parameters_error,
     },
-    controllers::check_id_valid,
+    controllers::{check_id_valid, check_track_filter_valid},
+    converter::convert_track_filter_to_string,
+    domain_models::TrackFilter,
+    events::{publish_event, CollectionEvent},
 };
 use crate::{
     domain::errors::DomainError,
@@ -11,7 +14,7 @@ use crate::{
         database::{
             add_collection_to_parent,
             get_collection_id_by_deezer_id as get_collection_id_by_deezer_id_database,
-            remove_collection_to_parent,
+            remove_collection_to_parent, update_collection_dependency_filter,
         },
         errors::DatabaseError,
     },
@@ -20,22 +23,33 @@ use crate::{
 pub async fn add_collection_dependency(
     parent_id: &str,
     child_id: &str,
+    filter: Option<TrackFilter>,
 ) -> Result<bool, DomainError> {
     log_parameters_error(check_id_valid(parent_id.to_string()))?;
     log_parameters_error(check_id_valid(child_id.to_string()))?;
+    log_parameters_error(check_track_filter_valid(&filter))?;
     add_collection_if_not_in_database(parent_id).await?;
     add_collection_if_not_in_database(child_id).await?;
-    add_collection_dependency_to_database(parent_id, child_id)?;
+    add_collection_dependency_to_database(parent_id, child_id, &filter)?;
+    publish_event(CollectionEvent::DependencyAdded {
+        parent_id: parent_id.to_string(),
+        child_id: child_id.to_string(),
+    });
     return Ok(true);
 }
 
 fn add_collection_dependency_to_database(
     parent_id: &str,
     child_id: &str,
+    filter: &Option<TrackFilter>,
 ) -> Result<bool, DomainError> {
     let database_parent_id = get_collection_id_by_deezer_id(parent_id)?;
     let database_child_id = get_collection_id_by_deezer_id(child_id)?;
-    match add_collection_to_parent(&database_parent_id, &database_child_id) {
+    match add_collection_to_parent(
+        &database_parent_id,
+        &database_child_id,
+        convert_track_filter_to_string(filter).as_deref(),
+    ) {
         Ok(_) => {}
         Err(e) => {
             return Err(log_database_error(&format!(
@@ -77,7 +91,13 @@ pub fn remove_collection_dependency(parent_id: &str, child_id: &str) -> Result<b
     let database_parent_id = get_collection_id_by_deezer_id(parent_id)?;
     let database_child_id = get_collection_id_by_deezer_id(child_id)?;
     match remove_collection_to_parent(&database_parent_id, &database_child_id) {
-        Ok(value) => return Ok(value),
+        Ok(value) => {
+            publish_event(CollectionEvent::DependencyRemoved {
+                parent_id: parent_id.to_string(),
+                child_id: child_id.to_string(),
+            });
+            return Ok(value);
+        }
         Err(e) => {
             return Err(log_database_error(&format!(
                 "Error while removing {} from {} : {:?}",
@@ -86,3 +106,36 @@ pub fn remove_collection_dependency(parent_id: &str, child_id: &str) -> Result<b
         }
     }
 }
+
+// sets (or removes if empty) the filter applied on the tracks of the child collection
+pub fn set_collection_dependency_filter(
+    parent_id: &str,
+    child_id: &str,
+    filter: Option<TrackFilter>,
+) -> Result<bool, DomainError> {
+    log_parameters_error(check_id_valid(parent_id.to_string()))?;
+    log_parameters_error(check_id_valid(child_id.to_string()))?;
+    log_parameters_error(check_track_filter_valid(&filter))?;
+    let database_parent_id = get_collection_id_by_deezer_id(parent_id)?;
+    let database_child_id = get_collection_id_by_deezer_id(child_id)?;
+    match update_collection_dependency_filter(
+        &database_parent_id,
+        &database_child_id,
+        convert_track_filter_to_string(&filter).as_deref(),
+    ) {
+        Ok(true) => return Ok(true),
+        Ok(false) => {
+            log_parameters_error(format!(
+                "- collection {} is not a child of collection {}",
+                child_id, parent_id
+            ))?;
+            return Ok(false);
+        }
+        Err(e) => {
+            return Err(log_database_error(&format!(
+                "Error while setting the filter of {} in {} : {:?}",
+                database_child_id, database_parent_id, e
+            )));
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use super::{
+    collection_commons::{
+        get_collection_id_by_deezer_id, log_database_error, log_parameters_error,
+    },
+    controllers::{check_exclusion_valid, check_id_valid},
+    domain_models::{Exclusion, ExclusionKind, Track},
+    errors::DomainError,
+    track_filters::artist_matches,
+};
+use crate::infrastructure::database::{
+    add_collection_exclusion, get_collection_exclusions, remove_collection_exclusion,
+};
+
+const EXCLUSION_KIND_TRACK: &str = "track";
+const EXCLUSION_KIND_ARTIST: &str = "artist";
+
+pub fn add_exclusion(collection_id: &str, exclusion: Exclusion) -> Result<bool, DomainError> {
+    log_parameters_error(check_id_valid(collection_id.to_string()))?;
+    log_parameters_error(check_exclusion_valid(&exclusion))?;
+    let database_collection_id = get_collection_id_by_deezer_id(collection_id)?;
+    match add_collection_exclusion(
+        &database_collection_id,
+        convert_exclusion_kind_to_string(&exclusion.kind),
+        exclusion.value.trim(),
+    ) {
+        Ok(value) => return Ok(value),
+        Err(e) => {
+            return Err(log_database_error(&format!(
+                "Error while adding exclusion {:?} to collection {} : {:?}",
+                exclusion, collection_id, e
+            )));
+        }
+    }
+}
+
+pub fn remove_exclusion(collection_id: &str, exclusion: Exclusion) -> Result<bool, DomainError> {
+    log_parameters_error(check_id_valid(collection_id.to_string()))?;
+    log_parameters_error(check_exclusion_valid(&exclusion))?;
+    let database_collection_id = get_collection_id_by_deezer_id(collection_id)?;
+    match remove_collection_exclusion(
+        &database_collection_id,
+        convert_exclusion_kind_to_string(&exclusion.kind),
+        exclusion.value.trim(),
+    ) {
+        Ok(value) => return Ok(value),
+        Err(e) => {
+            return Err(log_database_error(&format!(
+                "Error while removing exclusion {:?} from collection {} : {:?}",
+                exclusion, collection_id, e
+            )));
+        }
+    }
+}
+
+pub fn get_exclusions(collection_id: &str) -> Result<Vec<Exclusion>, DomainError> {
+    log_parameters_error(check_id_valid(collection_id.to_string()))?;
+    let database_collection_id = get_collection_id_by_deezer_id(collection_id)?;
+    match get_collection_exclusions(&database_collection_id) {
+        Ok(exclusions) => {
+            return Ok(exclusions
+                .into_iter()
+                .filter_map(|exclusion| {
+                    convert_string_to_exclusion_kind(&exclusion.kind).map(|kind| Exclusion {
+                        kind,
+                        value: exclusion.value,
+                    })
+                })
+                .collect::<Vec<_>>());
+        }
+        Err(e) => {
+            return Err(log_database_error(&format!(
+                "Error while getting the exclusions of collection {} : {:?}",
+                collection_id, e
+            )));
+        }
+    }
+}
+
+// returns the first exclusion matching the track, if any
+pub fn find_exclusion<'a>(exclusions: &'a [Exclusion], track: &Track) -> Option<&'a Exclusion> {
+    return exclusions.iter().find(|exclusion| match exclusion.kind {
+        ExclusionKind::Track => exclusion.value == track.deezer_id,
+        ExclusionKind::Artist => artist_matches(track, &exclusion.value),
+    });
+}
+
+fn convert_exclusion_kind_to_string(kind: &ExclusionKind) -> &'static str {
+    match kind {
+        ExclusionKind::Track => return EXCLUSION_KIND_TRACK,
+        ExclusionKind::Artist => return EXCLUSION_KIND_ARTIST,
+    }
+}
+
+fn convert_string_to_exclusion_kind(kind: &str) -> Option<ExclusionKind> {
+    match kind {
+        EXCLUSION_KIND_TRACK => return Some(ExclusionKind::Track),
+        EXCLUSION_KIND_ARTIST => return Some(ExclusionKind::Artist),
+        _ => return None,
+    }
+}
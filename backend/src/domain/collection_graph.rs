@@ -0,0 +1,140 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use super::{collection_commons::log_database_error, errors::DomainError};
+use crate::infrastructure::{
+    database::{get_collection_dependencies, list_collections},
+    database_models::CollectionDependencyDatabase,
+};
+
+// the collections by deezer id, and the dependencies between them
+#[derive(Debug, Clone, Default)]
+pub struct CollectionGraph {
+    pub collections: Vec<String>,
+    pub children: HashMap<String, Vec<String>>,
+    pub parents: HashMap<String, Vec<String>>,
+}
+
+pub fn get_collection_graph() -> Result<CollectionGraph, DomainError> {
+    let collections = match list_collections() {
+        Ok(collections) => collections
+            .into_iter()
+            .map(|collection| collection.deezer_id)
+            .collect::<Vec<_>>(),
+        Err(e) => {
+            return Err(log_database_error(&format!(
+                "Error while listing the collections of the graph : {:?}",
+                e
+            )));
+        }
+    };
+    match get_collection_dependencies() {
+        Ok(dependencies) => return Ok(build_collection_graph(collections, dependencies)),
+        Err(e) => {
+            return Err(log_database_error(&format!(
+                "Error while getting the dependencies of the graph : {:?}",
+                e
+            )));
+        }
+    }
+}
+
+// builds the graph from the collection deezer ids and the dependencies between them
+pub fn build_collection_graph(
+    collections: Vec<String>,
+    dependencies: Vec<CollectionDependencyDatabase>,
+) -> CollectionGraph {
+    let mut graph = CollectionGraph {
+        collections,
+        ..Default::default()
+    };
+    for dependency in dependencies.into_iter() {
+        graph
+            .children
+            .entry(dependency.parent_deezer_id.clone())
+            .or_default()
+            .push(dependency.child_deezer_id.clone());
+        graph
+            .parents
+            .entry(dependency.child_deezer_id)
+            .or_default()
+            .push(dependency.parent_deezer_id);
+    }
+    return graph;
+}
+
+pub fn get_children<'a>(graph: &'a CollectionGraph, id: &str) -> &'a [String] {
+    return graph
+        .children
+        .get(id)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+}
+
+pub fn get_parents<'a>(graph: &'a CollectionGraph, id: &str) -> &'a [String] {
+    return graph.parents.get(id).map(Vec::as_slice).unwrap_or_default();
+}
+
+// every collection reachable through the children, including the collection itself if it is in a cycle
+pub fn get_descendants(graph: &CollectionGraph, id: &str) -> Vec<String> {
+    return get_reachable(&graph.children, id);
+}
+
+// every collection reachable through the parents, including the collection itself if it is in a cycle
+pub fn get_ancestors(graph: &CollectionGraph, id: &str) -> Vec<String> {
+    return get_reachable(&graph.parents, id);
+}
+
+pub fn is_in_cycle(graph: &CollectionGraph, id: &str) -> bool {
+    return get_descendants(graph, id).iter().any(|el| el == id);
+}
+
+// a dependency is part of a cycle if the parent can be reached from the child
+pub fn is_cycle_dependency(graph: &CollectionGraph, parent_id: &str, child_id: &str) -> bool {
+    return parent_id == child_id
+        || get_descendants(graph, child_id)
+            .iter()
+            .any(|el| el == parent_id);
+}
+
+// distance from the nearest collection without parents.
+// Not set for the collections only reachable from a cycle
+pub fn get_depth(graph: &CollectionGraph, id: &str) -> Option<usize> {
+    let mut depths: HashMap<&str, usize> = HashMap::new();
+    let mut queue: VecDeque<&str> = graph
+        .collections
+        .iter()
+        .filter(|collection| get_parents(graph, collection).is_empty())
+        .map(String::as_str)
+        .collect::<VecDeque<_>>();
+    for root in queue.iter() {
+        depths.insert(root, 0);
+    }
+    while let Some(current) = queue.pop_front() {
+        let depth = depths[current];
+        if current == id {
+            return Some(depth);
+        }
+        for child in get_children(graph, current).iter() {
+            if !depths.contains_key(child.as_str()) {
+                depths.insert(child, depth + 1);
+                queue.push_back(child);
+            }
+        }
+    }
+    return None;
+}
+
+fn get_reachable(edges: &HashMap<String, Vec<String>>, id: &str) -> Vec<String> {
+    let mut reachable: Vec<String> = Vec::new();
+    let mut visited: HashSet<&str> = HashSet::new();
+    let mut queue: VecDeque<&str> = VecDeque::from([id]);
+    while let Some(current) = queue.pop_front() {
+        for next in edges.get(current).into_iter().flatten() {
+            if visited.insert(next) {
+                reachable.push(next.clone());
+                queue.push_back(next);
+            }
+        }
+    }
+    return reachable;
+}
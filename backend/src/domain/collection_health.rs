@@ -0,0 +1,175 @@
+use super::{
+    collection_commons::log_database_error,
+    collection_management::remove_collection,
+    converter::{convert_collection_origin_to_string, convert_string_to_collection_origin},
+    domain_models::{
+        CollectionHealthIssue, CollectionOrigin, DanglingDependency, HealthAction, HealthIssue,
+        HealthReport,
+    },
+    errors::DomainError,
+};
+use crate::{
+    common::common::get_env_variable,
+    infrastructure::{
+        database::{
+            get_dangling_dependencies, list_collections, remove_collection_dependency_by_id,
+            update_collection_owner,
+        },
+        database_models::CollectionDatabase,
+        deezer::get_playlist_summary,
+        deezer_models::PlaylistSummaryResponse,
+    },
+};
+
+// error code returned by deezer when the playlist does not exist
+pub const DEEZER_ERROR_CODE_NO_DATA: u64 = 800;
+
+// checks every collection against deezer, and every dependency against the collections
+// prune removes the collections whose playlist was deleted and the dangling dependencies
+// repair records the current owner of the playlists, and stops considering a playlist created by this app if it changed owner
+pub async fn check_collections_health(
+    prune: bool,
+    repair: bool,
+) -> Result<HealthReport, DomainError> {
+    let mut report = HealthReport::default();
+    let collections = match list_collections() {
+        Ok(collections) => collections,
+        Err(e) => {
+            return Err(log_database_error(&format!(
+                "Error while listing the collections to check : {:?}",
+                e
+            )));
+        }
+    };
+    for collection in collections.into_iter() {
+        let (issue, owner_id) = get_collection_health(&collection).await;
+        if let Some(owner_id) = owner_id {
+            if repair && collection.owner_id.as_ref() != Some(&owner_id) {
+                save_collection_owner(&collection, &owner_id)?;
+                report.actions.push(HealthAction::OwnerUpdated {
+                    deezer_id: collection.deezer_id.clone(),
+                    owner_id,
+                });
+            }
+        }
+        if let Some(issue) = issue {
+            if prune && matches!(issue, HealthIssue::Deleted) {
+                remove_collection(&collection.deezer_id)?;
+                report.actions.push(HealthAction::CollectionPruned {
+                    deezer_id: collection.deezer_id.clone(),
+                });
+            }
+            report.collections.push(CollectionHealthIssue {
+                deezer_id: collection.deezer_id,
+                name: collection.name,
+                issue,
+            });
+        }
+    }
+    // pruned collections remove their dependencies, so the dangling ones are looked for afterwards
+    report.dangling_dependencies = get_dangling_collection_dependencies()?;
+    if prune {
+        for dependency in report.dangling_dependencies.iter() {
+            if let Err(e) = remove_collection_dependency_by_id(&dependency.dependency_id) {
+                return Err(log_database_error(&format!(
+                    "Error while pruning the dependency {} : {:?}",
+                    dependency.dependency_id, e
+                )));
+            }
+            report.actions.push(HealthAction::DependencyPruned {
+                dependency_id: dependency.dependency_id,
+            });
+        }
+    }
+    return Ok(report);
+}
+
+// returns the issue of the collection if any, and the current owner of its playlist if it can be read
+async fn get_collection_health(
+    collection: &CollectionDatabase,
+) -> (Option<HealthIssue>, Option<String>) {
+    let playlist = match get_playlist_summary(&collection.deezer_id).await {
+        Ok(PlaylistSummaryResponse::Playlist(playlist)) => playlist,
+        Ok(PlaylistSummaryResponse::Error(response)) => {
+            if response.error.code == DEEZER_ERROR_CODE_NO_DATA {
+                return (Some(HealthIssue::Deleted), None);
+            }
+            return (
+                Some(HealthIssue::Unreadable {
+                    error: response.error.message,
+                }),
+                None,
+            );
+        }
+        Err(e) => {
+            return (
+                Some(HealthIssue::Unreadable {
+                    error: e.to_string(),
+                }),
+                None,
+            );
+        }
+    };
+    let owner_id = playlist.creator.id.to_string();
+    if let Some(previous_owner_id) = &collection.owner_id {
+        if *previous_owner_id != owner_id {
+            return (
+                Some(HealthIssue::OwnerChanged {
+                    previous_owner_id: previous_owner_id.clone(),
+                    owner_id: owner_id.clone(),
+                }),
+                Some(owner_id),
+            );
+        }
+    }
+    if !playlist.public && owner_id != get_env_variable("DEEZER_USER_ID") {
+        return (Some(HealthIssue::Private), Some(owner_id));
+    }
+    return (None, Some(owner_id));
+}
+
+fn save_collection_owner(
+    collection: &CollectionDatabase,
+    owner_id: &str,
+) -> Result<bool, DomainError> {
+    // a playlist that now belongs to someone else must not be deleted by this app anymore
+    let origin = if owner_id == get_env_variable("DEEZER_USER_ID") {
+        convert_string_to_collection_origin(&collection.origin)
+    } else {
+        CollectionOrigin::Imported
+    };
+    match update_collection_owner(
+        &collection.deezer_id,
+        owner_id,
+        convert_collection_origin_to_string(&origin),
+    ) {
+        Ok(value) => return Ok(value),
+        Err(e) => {
+            return Err(log_database_error(&format!(
+                "Error while saving the owner of collection {} : {:?}",
+                collection.deezer_id, e
+            )));
+        }
+    }
+}
+
+fn get_dangling_collection_dependencies() -> Result<Vec<DanglingDependency>, DomainError> {
+    match get_dangling_dependencies() {
+        Ok(dependencies) => {
+            return Ok(dependencies
+                .into_iter()
+                .map(|dependency| DanglingDependency {
+                    dependency_id: dependency.id,
+                    parent_id: dependency.parent_deezer_id,
+                    child_id: dependency.child_deezer_id,
+                })
+                .collect::<Vec<_>>());
+        }
+        Err(e) => {
+            return Err(log_database_error(&format!(
+                "Error while getting the dangling dependencies : {:?}",
+                e
+            )));
+        }
+    }
+}
@@ -0,0 +1,304 @@
+use std::collections::HashSet;
+
+use log::error;
+use serde::Deserialize;
+
+use super::{
+    collection_commons::{
+        convert_string_to_u64, create_collection_from_playlist, log_database_error,
+        log_parameters_error,
+    },
+    collection_graph::{
+        build_collection_graph, get_children, get_collection_graph, is_cycle_dependency,
+        CollectionGraph,
+    },
+    collection_management::remove_collection,
+    controllers::check_id_valid,
+    domain_models::{HierarchyDependency, HierarchyFormat, HierarchyPlan},
+    errors::DomainError,
+    events::{publish_event, CollectionEvent},
+};
+use crate::infrastructure::{
+    database::apply_planned_dependency_mutations, database_models::DependencyMutationDatabase,
+};
+
+// e.g. in yaml
+// collections:
+//   - id: "123456"
+//     children: ["456789", 789012]
+//   - id: 789012
+// the unknown keys are rejected, so that a typo does not read as a file without collections
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct HierarchyFile {
+    collections: Vec<HierarchyFileCollection>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct HierarchyFileCollection {
+    id: HierarchyFileId,
+    #[serde(default)]
+    children: Vec<HierarchyFileId>,
+}
+
+// the ids can be written with or without quotes
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum HierarchyFileId {
+    Number(u64),
+    Text(String),
+}
+
+impl HierarchyFileId {
+    fn to_deezer_id(&self) -> String {
+        match self {
+            HierarchyFileId::Number(id) => return id.to_string(),
+            HierarchyFileId::Text(id) => return id.trim().to_string(),
+        }
+    }
+}
+
+// the collections in order of appearance in the file, and the dependencies between them
+struct Hierarchy {
+    collections: Vec<String>,
+    dependencies: Vec<HierarchyDependency>,
+}
+
+// lists the changes that apply_hierarchy would make, without making them
+pub fn plan_hierarchy(
+    format: &HierarchyFormat,
+    content: &str,
+    prune: bool,
+) -> Result<HierarchyPlan, DomainError> {
+    let hierarchy = parse_hierarchy(format, content)?;
+    return get_hierarchy_plan(&hierarchy, prune);
+}
+
+// makes the collections and their dependencies match the file
+// the missing collections are created first from their deezer playlist, a failure leaving the other changes undone.
+// The dependencies are then checked again and changed in one transaction, and prune finally removes the collections
+// that are not in the file, one by one
+pub async fn apply_hierarchy(
+    format: &HierarchyFormat,
+    content: &str,
+    prune: bool,
+) -> Result<HierarchyPlan, DomainError> {
+    let hierarchy = parse_hierarchy(format, content)?;
+    let mut plan = get_hierarchy_plan(&hierarchy, prune)?;
+    for id in plan.collections_to_add.iter() {
+        create_collection_from_playlist(&convert_string_to_u64(id)).await?;
+    }
+    let mut failures = String::new();
+    let applied = apply_planned_dependency_mutations(|collections, dependencies| {
+        let graph = build_collection_graph(collections, dependencies);
+        let dependencies_plan = build_hierarchy_plan(&hierarchy, &graph, prune);
+        failures = check_hierarchy_plan_cycles(&dependencies_plan, &graph);
+        if !failures.is_empty() {
+            return None;
+        }
+        let mutations = dependencies_plan
+            .dependencies_to_remove
+            .iter()
+            .map(|dependency| DependencyMutationDatabase::Remove {
+                parent_deezer_id: dependency.parent_id.clone(),
+                child_deezer_id: dependency.child_id.clone(),
+            })
+            .chain(
+                dependencies_plan
+                    .dependencies_to_add
+                    .iter()
+                    .map(|dependency| DependencyMutationDatabase::Add {
+                        parent_deezer_id: dependency.parent_id.clone(),
+                        child_deezer_id: dependency.child_id.clone(),
+                        filter: None,
+                    }),
+            )
+            .collect::<Vec<_>>();
+        plan.collections_to_remove = dependencies_plan.collections_to_remove;
+        plan.dependencies_to_add = dependencies_plan.dependencies_to_add;
+        plan.dependencies_to_remove = dependencies_plan.dependencies_to_remove;
+        return Some(mutations);
+    });
+    match applied {
+        Ok(true) => {}
+        Ok(false) => {
+            log_parameters_error(failures)?;
+        }
+        Err(e) => {
+            return Err(log_database_error(&format!(
+                "Error while applying the dependencies of the hierarchy : {:?}",
+                e
+            )));
+        }
+    }
+    for dependency in plan.dependencies_to_remove.iter() {
+        publish_event(CollectionEvent::DependencyRemoved {
+            parent_id: dependency.parent_id.clone(),
+            child_id: dependency.child_id.clone(),
+        });
+    }
+    for dependency in plan.dependencies_to_add.iter() {
+        publish_event(CollectionEvent::DependencyAdded {
+            parent_id: dependency.parent_id.clone(),
+            child_id: dependency.child_id.clone(),
+        });
+    }
+    // removing a collection also removes its dependencies
+    for id in plan.collections_to_remove.iter() {
+        remove_collection(id)?;
+    }
+    return Ok(plan);
+}
+
+fn parse_hierarchy(format: &HierarchyFormat, content: &str) -> Result<Hierarchy, DomainError> {
+    let hierarchy_file = match format {
+        HierarchyFormat::Yaml => {
+            serde_yaml::from_str::<HierarchyFile>(content).map_err(|e| e.to_string())
+        }
+        HierarchyFormat::Toml => {
+            toml::from_str::<HierarchyFile>(content).map_err(|e| e.to_string())
+        }
+    };
+    let hierarchy_file = match hierarchy_file {
+        Ok(hierarchy_file) => hierarchy_file,
+        Err(e) => {
+            let message = format!("the hierarchy file can not be read : {}", e);
+            error!("Handler - error in input parameters : {}", message);
+            return Err(DomainError::DomainParamError(message));
+        }
+    };
+    let mut hierarchy = Hierarchy {
+        collections: Vec::new(),
+        dependencies: Vec::new(),
+    };
+    let mut failures = String::new();
+    if hierarchy_file.collections.is_empty() {
+        failures.push_str("- the hierarchy file should contain at least one collection\n");
+    }
+    for collection in hierarchy_file.collections.iter() {
+        let parent_id = collection.id.to_deezer_id();
+        for id in std::iter::once(&collection.id).chain(collection.children.iter()) {
+            let id = id.to_deezer_id();
+            if !check_id_valid(id.clone()).is_empty() {
+                failures.push_str(&format!("- {} is not a valid collection id\n", id));
+            } else if !hierarchy.collections.contains(&id) {
+                hierarchy.collections.push(id);
+            }
+        }
+        for child in collection.children.iter() {
+            let dependency = HierarchyDependency {
+                parent_id: parent_id.clone(),
+                child_id: child.to_deezer_id(),
+            };
+            if dependency.parent_id == dependency.child_id {
+                failures.push_str(&format!(
+                    "- collection {} can not be its own child\n",
+                    dependency.parent_id
+                ));
+            } else if !hierarchy.dependencies.contains(&dependency) {
+                hierarchy.dependencies.push(dependency);
+            }
+        }
+    }
+    if !failures.is_empty() {
+        error!("Handler - error in input parameters : {}", failures);
+        return Err(DomainError::DomainParamError(failures));
+    }
+    return Ok(hierarchy);
+}
+
+fn get_hierarchy_plan(hierarchy: &Hierarchy, prune: bool) -> Result<HierarchyPlan, DomainError> {
+    let graph = get_collection_graph()?;
+    let plan = build_hierarchy_plan(hierarchy, &graph, prune);
+    log_parameters_error(check_hierarchy_plan_cycles(&plan, &graph))?;
+    return Ok(plan);
+}
+
+fn build_hierarchy_plan(
+    hierarchy: &Hierarchy,
+    graph: &CollectionGraph,
+    prune: bool,
+) -> HierarchyPlan {
+    let wanted_collections = hierarchy.collections.iter().collect::<HashSet<_>>();
+    let existing_collections = graph.collections.iter().collect::<HashSet<_>>();
+    let wanted_dependencies = hierarchy.dependencies.iter().collect::<HashSet<_>>();
+    let existing_dependencies = get_existing_dependencies(graph);
+    let collections_to_remove = graph
+        .collections
+        .iter()
+        .filter(|id| prune && !wanted_collections.contains(id))
+        .cloned()
+        .collect::<Vec<_>>();
+    return HierarchyPlan {
+        collections_to_add: hierarchy
+            .collections
+            .iter()
+            .filter(|id| !existing_collections.contains(id))
+            .cloned()
+            .collect::<Vec<_>>(),
+        dependencies_to_add: hierarchy
+            .dependencies
+            .iter()
+            .filter(|dependency| !existing_dependencies.contains(dependency))
+            .cloned()
+            .collect::<Vec<_>>(),
+        // the file gives all the children of its collections, even the ones it does not list.
+        // The dependencies of the removed collections are removed with them
+        dependencies_to_remove: existing_dependencies
+            .iter()
+            .filter(|dependency| {
+                !wanted_dependencies.contains(dependency)
+                    && wanted_collections.contains(&dependency.parent_id)
+                    && !collections_to_remove.contains(&dependency.child_id)
+            })
+            .cloned()
+            .collect::<Vec<_>>(),
+        collections_to_remove,
+    };
+}
+
+fn get_existing_dependencies(graph: &CollectionGraph) -> Vec<HierarchyDependency> {
+    return graph
+        .collections
+        .iter()
+        .flat_map(|parent_id| {
+            get_children(graph, parent_id)
+                .iter()
+                .map(|child_id| HierarchyDependency {
+                    parent_id: parent_id.clone(),
+                    child_id: child_id.clone(),
+                })
+        })
+        .collect::<Vec<_>>();
+}
+
+// returns the added dependencies that would be part of a cycle once the plan applied, empty if none
+fn check_hierarchy_plan_cycles(plan: &HierarchyPlan, graph: &CollectionGraph) -> String {
+    let mut planned_graph = CollectionGraph::default();
+    for dependency in get_existing_dependencies(graph)
+        .iter()
+        .filter(|dependency| {
+            !plan.dependencies_to_remove.contains(dependency)
+                && !plan.collections_to_remove.contains(&dependency.parent_id)
+                && !plan.collections_to_remove.contains(&dependency.child_id)
+        })
+        .chain(plan.dependencies_to_add.iter())
+    {
+        planned_graph
+            .children
+            .entry(dependency.parent_id.clone())
+            .or_default()
+            .push(dependency.child_id.clone());
+    }
+    let mut res: String = "".to_owned();
+    for dependency in plan.dependencies_to_add.iter() {
+        if is_cycle_dependency(&planned_graph, &dependency.parent_id, &dependency.child_id) {
+            res.push_str(&format!(
+                "- adding collection {} to collection {} would create a cycle\n",
+                dependency.child_id, dependency.parent_id
+            ));
+        }
+    }
+    return res;
+}
@@ -0,0 +1,105 @@
+use std::collections::HashSet;
+
+use super::{
+    collection_commons::{
+        convert_string_to_u64, create_collection_from_playlist, log_database_error,
+        log_deezer_error, log_parameters_error,
+    },
+    controllers::check_id_valid,
+    deezer_links::get_canonical_playlist_url,
+    domain_models::{FailedImport, ImportReport, UserPlaylist},
+    errors::DomainError,
+};
+use crate::infrastructure::{
+    database::list_collections, deezer::get_user_playlists as get_user_playlists_page,
+};
+
+// returns all the playlists of the user, going through every page
+pub async fn get_user_playlists() -> Result<Vec<UserPlaylist>, DomainError> {
+    let collections_ids = match list_collections() {
+        Ok(collections) => collections
+            .into_iter()
+            .map(|collection| collection.deezer_id)
+            .collect::<HashSet<_>>(),
+        Err(e) => {
+            return Err(log_database_error(&format!(
+                "Error while listing the collections : {:?}",
+                e
+            )));
+        }
+    };
+    let mut user_playlists: Vec<UserPlaylist> = Vec::new();
+    let mut index: u64 = 0;
+    loop {
+        let page = match get_user_playlists_page(&index).await {
+            Ok(page) => page,
+            Err(e) => {
+                return Err(log_deezer_error(&format!(
+                    "Error getting the playlists of the user from index {} : {:?}",
+                    index, e
+                )));
+            }
+        };
+        index += page.data.len() as u64;
+        let is_last_page = page.next.is_none() || page.data.is_empty();
+        for playlist in page.data.into_iter() {
+            let deezer_id = playlist.id.to_string();
+            user_playlists.push(UserPlaylist {
+                already_imported: collections_ids.contains(&deezer_id),
+                deezer_id,
+                name: playlist.title,
+                url: get_canonical_playlist_url(&playlist.id),
+            });
+        }
+        if is_last_page {
+            return Ok(user_playlists);
+        }
+    }
+}
+
+// registers the selected playlists of the user as collections, or all of them if none are selected
+pub async fn import_user_playlists(
+    playlist_ids: Option<Vec<String>>,
+) -> Result<ImportReport, DomainError> {
+    if let Some(playlist_ids) = &playlist_ids {
+        for playlist_id in playlist_ids.iter() {
+            log_parameters_error(check_id_valid(playlist_id.clone()))?;
+        }
+    }
+    let user_playlists = get_user_playlists().await?;
+    let mut report = ImportReport::default();
+    if let Some(playlist_ids) = &playlist_ids {
+        report.not_found = playlist_ids
+            .iter()
+            .filter(|playlist_id| {
+                !user_playlists
+                    .iter()
+                    .any(|playlist| playlist.deezer_id == **playlist_id)
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+    }
+    let selected_playlists = user_playlists.into_iter().filter(|playlist| {
+        playlist_ids.as_ref().map_or(true, |playlist_ids| {
+            playlist_ids.contains(&playlist.deezer_id)
+        })
+    });
+    for playlist in selected_playlists {
+        if playlist.already_imported {
+            report.already_existing.push(playlist);
+            continue;
+        }
+        // a playlist that can not be imported should not prevent importing the other ones
+        match create_collection_from_playlist(&convert_string_to_u64(&playlist.deezer_id)).await {
+            Ok(_) => report.imported.push(UserPlaylist {
+                already_imported: true,
+                ..playlist
+            }),
+            Err(e) => report.failed.push(FailedImport {
+                deezer_id: playlist.deezer_id,
+                error: e.to_string(),
+            }),
+        }
+    }
+    return Ok(report);
+}
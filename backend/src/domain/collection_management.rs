@@ -1,19 +1,38 @@
 use super::collection_commons::{
     create_collection_from_playlist, create_new_playlist, get_collection_id_by_deezer_id,
-    get_playlist_id_from_url, log_parameters_error,
+    get_playlists, log_parameters_error,
 };
-use super::controllers::{check_id_valid, check_init_collections};
-use super::domain_models::{self, Collection, CollectionListElement, Track};
+use super::collection_exclusions::{find_exclusion, get_exclusions};
+use super::collection_metadata::{
+    get_tags_by_collection, record_refresh_failure, record_refresh_success,
+};
+use super::collection_refresh_policy::get_collections_not_due;
+use super::controllers::{check_collection_list_query, check_id_valid, check_init_collections};
+use super::converter::{
+    convert_collection, convert_collection_list_query, convert_collection_metadata,
+    convert_string_to_collection_kind, convert_string_to_track_filter,
+};
+use super::deezer_links::resolve_playlist_id;
+use super::domain_models::{
+    self, ChildCollection, Collection, CollectionKind, CollectionListElement, CollectionListPage,
+    CollectionListQuery, CollectionOrigin, RefreshReport, SkipReason, SkippedTrack, Track,
+};
+use super::duplicates::{find_duplicate, get_tracks_isrcs, get_tracks_needing_isrc};
 use super::errors::DomainError;
+use super::events::{publish_event, CollectionEvent};
+use super::smart_collections::get_smart_collection_tracks;
+use super::track_filters::partition_tracks;
+use super::track_ordering::{apply_ordering_policy, sort_tracks};
 use crate::common::common::get_env_variable;
 use crate::domain::collection_commons::{
-    convert_string_to_u64, get_playlist, log_database_error, log_deezer_error,
+    convert_string_to_u64, get_playlist, log_database_error, log_deezer_error, log_forbidden_error,
 };
 use crate::infrastructure::database::{
-    clear_database, get_child_collections, remove_collection_in_database,
+    clear_database, get_child_collection_dependencies, get_child_collections,
+    list_collections_filtered, remove_collection_in_database,
 };
-use crate::infrastructure::deezer::add_tracks_to_playlist;
-use crate::infrastructure::{self, database};
+use crate::infrastructure::deezer::{add_tracks_to_playlist, delete_playlist};
+use crate::infrastructure::database;
 use log::error;
 
 pub async fn init_collections(options: domain_models::InitCollection) -> Result<bool, DomainError> {
@@ -21,29 +40,47 @@ pub async fn init_collections(options: domain_models::InitCollection) -> Result<
     // if from playlist, fill the collection with the playlist track
     match options.from_playlist {
         Some(url) => {
-            return create_collection_from_playlist(&get_playlist_id_from_url(url)).await;
+            return create_collection_from_playlist(&resolve_playlist_id(&url).await?).await;
         }
         None => {
-            return create_new_playlist(&options.name).await;
+            create_new_playlist(&options.name).await?;
+            return Ok(true);
         }
     }
 }
 
 pub fn list_collections() -> Result<Vec<CollectionListElement>, DomainError> {
-    match infrastructure::database::list_collections() {
-        Ok(collections_database) => {
-            let collections_handler = collections_database
+    return Ok(list_collections_page(&CollectionListQuery::default())?.collections);
+}
+
+// searches, sorts and paginates the collections
+pub fn list_collections_page(
+    query: &CollectionListQuery,
+) -> Result<CollectionListPage, DomainError> {
+    log_parameters_error(check_collection_list_query(query))?;
+    let mut tags_by_collection = get_tags_by_collection()?;
+    match list_collections_filtered(&convert_collection_list_query(query)) {
+        Ok((collections_database, total_count)) => {
+            let collections = collections_database
                 .into_iter()
                 .map(|collection| {
+                    let tags = tags_by_collection
+                        .remove(&collection.deezer_id)
+                        .unwrap_or_default();
                     let collection_element = CollectionListElement {
+                        metadata: convert_collection_metadata(&collection, tags),
                         name: collection.name,
                         deezer_id: collection.deezer_id,
                         url: collection.url,
+                        kind: convert_string_to_collection_kind(&collection.kind),
                     };
                     return collection_element;
                 })
                 .collect::<Vec<_>>();
-            Ok(collections_handler)
+            Ok(CollectionListPage {
+                collections,
+                total_count,
+            })
         }
         Err(e) => {
             return Err(log_database_error(&format!(
@@ -57,47 +94,49 @@ pub fn list_collections() -> Result<Vec<CollectionListElement>, DomainError> {
 pub async fn get_collection_tracks_excluding_children(id: &str) -> Result<Vec<Track>, DomainError> {
     log_parameters_error(check_id_valid(id.to_string()))?;
     let playlist = get_playlist(&convert_string_to_u64(id)).await?;
-    let children_collections = get_direct_children_collections(id)?;
+    let children_collections_ids = get_direct_children_collections(id)?
+        .into_iter()
+        .map(|children_col| children_col.deezer_id)
+        .collect::<Vec<_>>();
     let mut tracks = playlist.tracks;
-    for children_col in children_collections.clone().into_iter() {
-        let playlist =
-            get_playlist(&convert_string_to_u64(children_col.deezer_id.as_str())).await?;
+    for playlist in get_playlists(&children_collections_ids).await?.into_iter() {
         for track in playlist.tracks.into_iter() {
             let track_index = tracks
                 .clone()
                 .into_iter()
                 .position(|el| el.deezer_id == track.deezer_id);
-            if track_index.is_some() {
-                tracks.remove(track_index.unwrap());
+            if let Some(track_index) = track_index {
+                tracks.remove(track_index);
             }
         }
     }
     return Ok(tracks);
 }
 
-// will return the basic children collections (no tracks or other children collections)
-pub fn get_direct_children_collections(id: &str) -> Result<Vec<Collection>, DomainError> {
+// will return the basic children collections (no tracks or other children collections), with their filters
+pub fn get_direct_children_collections(id: &str) -> Result<Vec<ChildCollection>, DomainError> {
     log_parameters_error(check_id_valid(id.to_string()))?;
     let parent_id = get_collection_id_by_deezer_id(id)?;
-    let children_collections: Vec<Collection>;
-    match get_child_collections(&parent_id) {
-        Ok(collections) => {
-            children_collections = collections
+    let children_collections: Vec<ChildCollection> =
+        match get_child_collection_dependencies(&parent_id) {
+            Ok(collections) => collections
                 .into_iter()
-                .map(|collection| Collection {
-                    name: collection.name,
-                    deezer_id: collection.deezer_id,
-                    url: collection.url,
+                .map(|collection| {
+                    Ok(ChildCollection {
+                        name: collection.name,
+                        deezer_id: collection.deezer_id,
+                        url: collection.url,
+                        filter: convert_string_to_track_filter(collection.filter)?,
+                    })
                 })
-                .collect::<Vec<_>>();
-        }
-        Err(e) => {
-            return Err(log_database_error(&format!(
-                "Error while getting child collections of {} : {:?}",
-                parent_id, e
-            )));
-        }
-    }
+                .collect::<Result<Vec<_>, DomainError>>()?,
+            Err(e) => {
+                return Err(log_database_error(&format!(
+                    "Error while getting child collections of {} : {:?}",
+                    parent_id, e
+                )));
+            }
+        };
     return Ok(children_collections);
 }
 
@@ -105,11 +144,10 @@ pub async fn get_collection(id: &str) -> Result<Collection, DomainError> {
     log_parameters_error(check_id_valid(id.to_string()))?;
     match database::get_collection(id) {
         Ok(collection) => {
-            return Ok(Collection {
-                name: collection.name,
-                deezer_id: collection.deezer_id,
-                url: collection.url,
-            });
+            let tags = get_tags_by_collection()?
+                .remove(&collection.deezer_id)
+                .unwrap_or_default();
+            return Ok(convert_collection(collection, tags));
         }
         Err(e) => {
             return Err(log_database_error(&format!(
@@ -122,6 +160,76 @@ pub async fn get_collection(id: &str) -> Result<Collection, DomainError> {
 
 pub async fn refresh_collection(id: &str) -> Result<bool, DomainError> {
     log_parameters_error(check_id_valid(id.to_string()))?;
+    publish_event(CollectionEvent::RefreshStarted {
+        deezer_id: id.to_string(),
+    });
+    match add_children_tracks_to_collection(id).await {
+        Ok((tracks_added, track_count)) => {
+            record_refresh_success(id, tracks_added.len(), track_count);
+            publish_event(CollectionEvent::CollectionRefreshed {
+                deezer_id: id.to_string(),
+                tracks_added,
+            });
+            return Ok(true);
+        }
+        Err(e) => {
+            record_refresh_failure(id, &e.to_string());
+            publish_event(CollectionEvent::RefreshFailed {
+                deezer_id: id.to_string(),
+                error: e.to_string(),
+            });
+            return Err(e);
+        }
+    }
+}
+
+// returns the tracks that would be added to the collection by a refresh, without adding them
+pub async fn refresh_collection_dry_run(id: &str) -> Result<RefreshReport, DomainError> {
+    log_parameters_error(check_id_valid(id.to_string()))?;
+    return get_refresh_report(id).await;
+}
+
+// adds the tracks of the children collections missing in the collection,
+// and returns the added track ids with the number of tracks of the collection
+async fn add_children_tracks_to_collection(id: &str) -> Result<(Vec<String>, usize), DomainError> {
+    let collection = get_collection(id).await?;
+    let (refresh_report, track_count) = get_refresh_report_and_track_count(id).await?;
+    let tracks_to_add = refresh_report.tracks_to_add;
+    let tracks_to_add_ids = tracks_to_add
+        .iter()
+        .map(|track| track.deezer_id.clone())
+        .collect::<Vec<_>>();
+    match add_tracks_to_playlist(id, tracks_to_add_ids.clone()).await {
+        Ok(_) => {
+            // the tracks are already in the playlist, so the refresh succeeded even if they can not be ordered
+            if let Err(e) = apply_ordering_policy(id, &collection.ordering, &tracks_to_add).await {
+                publish_event(CollectionEvent::OrderingFailed {
+                    deezer_id: id.to_string(),
+                    error: e.to_string(),
+                });
+            }
+            let track_count = track_count + tracks_to_add_ids.len();
+            return Ok((tracks_to_add_ids, track_count));
+        }
+        Err(e) => {
+            return Err(log_deezer_error(&format!(
+                "Error while adding tracks to the playlist {} : {:?}",
+                id, e
+            )));
+        }
+    }
+}
+
+async fn get_refresh_report(id: &str) -> Result<RefreshReport, DomainError> {
+    let (refresh_report, _) = get_refresh_report_and_track_count(id).await?;
+    return Ok(refresh_report);
+}
+
+// computes the tracks missing in the collection, and the ones skipped. Also returns the current number of tracks of the collection
+pub async fn get_refresh_report_and_track_count(
+    id: &str,
+) -> Result<(RefreshReport, usize), DomainError> {
+    let collection = get_collection(id).await?;
     let playlist = get_playlist(&convert_string_to_u64(id)).await?;
     let parent_playlist_tracks_ids = playlist
         .clone()
@@ -129,42 +237,126 @@ pub async fn refresh_collection(id: &str) -> Result<bool, DomainError> {
         .into_iter()
         .map(|track| track.deezer_id)
         .collect::<Vec<_>>();
-    match get_child_collections(&get_collection_id_by_deezer_id(id)?) {
-        Ok(child_collections) => {
-            let mut tracks_to_add: Vec<String> = Vec::new();
-            for collection in child_collections.into_iter() {
-                let child_playlist =
-                    get_playlist(&convert_string_to_u64(collection.deezer_id.as_str())).await?;
-                for track in child_playlist.tracks.into_iter() {
-                    if !tracks_to_add.contains(&track.deezer_id)
-                        && !parent_playlist_tracks_ids.contains(&track.deezer_id)
-                    {
-                        tracks_to_add.push(track.deezer_id);
-                    }
-                }
+    let exclusions = get_exclusions(id)?;
+    let (candidate_tracks, mut filtered_tracks) = match collection.rule {
+        Some(rule) if collection.kind == CollectionKind::Smart => {
+            let smart_collection_tracks = get_smart_collection_tracks(id, &rule)
+                .await?
+                .into_iter()
+                .filter(|track| !parent_playlist_tracks_ids.contains(&track.deezer_id))
+                .collect::<Vec<_>>();
+            (smart_collection_tracks, Vec::new())
+        }
+        _ => get_children_candidate_tracks(id, &parent_playlist_tracks_ids).await?,
+    };
+    let isrcs = get_tracks_isrcs(&get_tracks_needing_isrc(
+        &candidate_tracks
+            .iter()
+            .filter(|track| find_exclusion(&exclusions, track).is_none())
+            .cloned()
+            .collect::<Vec<_>>(),
+        &playlist.tracks,
+    ))
+    .await?;
+    let track_count = playlist.tracks.len();
+    let mut known_tracks = playlist.tracks;
+    let mut tracks_to_add: Vec<Track> = Vec::new();
+    let mut skipped_tracks: Vec<SkippedTrack> = Vec::new();
+    for track in candidate_tracks.into_iter() {
+        if let Some(exclusion) = find_exclusion(&exclusions, &track) {
+            skipped_tracks.push(SkippedTrack {
+                track,
+                reason: SkipReason::Excluded {
+                    exclusion: exclusion.clone(),
+                },
+            });
+            continue;
+        }
+        // the same recording can exist under several deezer ids (album and single releases, remasters...)
+        match find_duplicate(&track, &isrcs, &known_tracks) {
+            Some((duplicate_track, matched_by)) => skipped_tracks.push(SkippedTrack {
+                reason: SkipReason::Duplicate {
+                    duplicate_of: duplicate_track.deezer_id.clone(),
+                    matched_by,
+                },
+                track,
+            }),
+            None => {
+                known_tracks.push(track.clone());
+                tracks_to_add.push(track);
             }
-            match add_tracks_to_playlist(id, tracks_to_add).await {
-                Ok(_) => return Ok(true),
-                Err(e) => {
-                    return Err(log_deezer_error(&format!(
-                        "Error while adding tracks to the playlist {} : {:?}",
-                        id, e
-                    )));
-                }
+        }
+    }
+    skipped_tracks.append(&mut filtered_tracks);
+    sort_tracks(&mut tracks_to_add, &collection.ordering.track_order);
+    return Ok((
+        RefreshReport {
+            deezer_id: id.to_string(),
+            tracks_to_add,
+            skipped_tracks,
+        },
+        track_count,
+    ));
+}
+
+// returns the tracks of the children collections missing in the parent, and the ones filtered out
+async fn get_children_candidate_tracks(
+    id: &str,
+    parent_playlist_tracks_ids: &[String],
+) -> Result<(Vec<Track>, Vec<SkippedTrack>), DomainError> {
+    let mut candidate_tracks: Vec<Track> = Vec::new();
+    let mut filtered_tracks: Vec<SkippedTrack> = Vec::new();
+    let children_collections = get_direct_children_collections(id)?;
+    let children_collections_ids = children_collections
+        .iter()
+        .map(|collection| collection.deezer_id.clone())
+        .collect::<Vec<_>>();
+    let children_playlists = get_playlists(&children_collections_ids).await?;
+    for (collection, child_playlist) in children_collections.into_iter().zip(children_playlists) {
+        let (matching_tracks, not_matching_tracks) =
+            partition_tracks(&collection.filter, child_playlist.tracks)?;
+        for track in matching_tracks.into_iter() {
+            if !parent_playlist_tracks_ids.contains(&track.deezer_id)
+                && !candidate_tracks
+                    .iter()
+                    .any(|el| el.deezer_id == track.deezer_id)
+            {
+                candidate_tracks.push(track);
             }
         }
-        Err(e) => {
-            return Err(log_database_error(&format!(
-                "Error while getting the children from collection {} : {:?}",
-                id, e
-            )));
+        for track in not_matching_tracks.into_iter() {
+            if !parent_playlist_tracks_ids.contains(&track.deezer_id)
+                && !filtered_tracks
+                    .iter()
+                    .any(|el| el.track.deezer_id == track.deezer_id)
+            {
+                filtered_tracks.push(SkippedTrack {
+                    track,
+                    reason: SkipReason::Filtered {
+                        child_id: collection.deezer_id.clone(),
+                    },
+                });
+            }
         }
     }
+    // a track filtered out of a child can still be added through another child
+    filtered_tracks.retain(|filtered_track| {
+        !candidate_tracks
+            .iter()
+            .any(|el| el.deezer_id == filtered_track.track.deezer_id)
+    });
+    return Ok((candidate_tracks, filtered_tracks));
 }
 
 pub async fn update_all_collections() -> Result<bool, DomainError> {
     let mut playlists_ids_to_update: Vec<String> = Vec::new();
-    let mut next_children_ids: Vec<String> = list_collections()?
+    let collections = list_collections()?;
+    let smart_collections_ids = collections
+        .iter()
+        .filter(|collection| collection.kind == CollectionKind::Smart)
+        .map(|collection| collection.deezer_id.clone())
+        .collect::<Vec<_>>();
+    let mut next_children_ids: Vec<String> = collections
         .into_iter()
         .map(|collection| collection.deezer_id)
         .collect::<Vec<_>>();
@@ -194,12 +386,17 @@ pub async fn update_all_collections() -> Result<bool, DomainError> {
             }
         }
         // Continue or exit loop
-        if children_ids.len() > 0 {
+        if !children_ids.is_empty() {
             next_children_ids = children_ids;
         } else {
             break;
         }
     }
+    // Smart collections are updated last, once the collections they query are up to date
+    playlists_ids_to_update.sort_by_key(|id| smart_collections_ids.contains(id));
+    // The disabled collections, and the ones already refreshed for their schedule, are skipped
+    let collections_not_due_ids = get_collections_not_due()?;
+    playlists_ids_to_update.retain(|id| !collections_not_due_ids.contains(id));
     // Update collections
     for id in playlists_ids_to_update.into_iter() {
         match refresh_collection(id.as_str()).await {
@@ -213,13 +410,18 @@ pub async fn update_all_collections() -> Result<bool, DomainError> {
 }
 
 fn get_max_depth() -> u64 {
-    return convert_string_to_u64(&get_env_variable("MAX_COLLECTION_DEPTH").as_str());
+    return convert_string_to_u64(get_env_variable("MAX_COLLECTION_DEPTH").as_str());
 }
 
 pub fn remove_collection(id: &str) -> Result<bool, DomainError> {
     log_parameters_error(check_id_valid(id.to_string()))?;
     match remove_collection_in_database(&get_collection_id_by_deezer_id(id)?) {
-        Ok(res) => return Ok(res),
+        Ok(res) => {
+            publish_event(CollectionEvent::CollectionRemoved {
+                deezer_id: id.to_string(),
+            });
+            return Ok(res);
+        }
         Err(e) => {
             return Err(log_database_error(&format!(
                 "Error while removing collection {} in database : {:?}",
@@ -229,6 +431,41 @@ pub fn remove_collection(id: &str) -> Result<bool, DomainError> {
     }
 }
 
+// removes the collection and deletes its deezer playlist, only if the playlist was created by this app for the current user
+pub async fn remove_collection_and_playlist(id: &str) -> Result<bool, DomainError> {
+    log_parameters_error(check_id_valid(id.to_string()))?;
+    let collection = get_collection(id).await?;
+    if collection.origin != CollectionOrigin::Created {
+        return Err(log_forbidden_error(&format!(
+            "Refusing to delete the playlist {} : it was not created by this app",
+            id
+        )));
+    }
+    let playlist = get_playlist(&convert_string_to_u64(id)).await?;
+    if playlist.creator_id.to_string() != get_env_variable("DEEZER_USER_ID") {
+        return Err(log_forbidden_error(&format!(
+            "Refusing to delete the playlist {} : it belongs to the user {}",
+            id, playlist.creator_id
+        )));
+    }
+    // the collection is only removed once deezer confirmed the deletion, so that no playlist is left behind
+    match delete_playlist(id).await {
+        Ok(true) => return remove_collection(id),
+        Ok(false) => {
+            return Err(log_deezer_error(&format!(
+                "Deezer did not delete the playlist {}",
+                id
+            )));
+        }
+        Err(e) => {
+            return Err(log_deezer_error(&format!(
+                "Error while deleting the playlist {} : {:?}",
+                id, e
+            )));
+        }
+    }
+}
+
 pub fn clear_data() -> Result<bool, DomainError> {
     match clear_database() {
         Ok(_) => return Ok(true),
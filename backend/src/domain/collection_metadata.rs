@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+
+use super::{
+    collection_commons::{
+        get_collection_id_by_deezer_id, get_current_timestamp, log_database_error,
+        log_parameters_error,
+    },
+    controllers::{check_collection_tags_valid, check_id_valid},
+    converter::convert_refresh_status_to_string,
+    domain_models::RefreshStatus,
+    errors::DomainError,
+};
+use crate::infrastructure::{
+    database::{
+        get_collections_tags, set_collection_tags, update_collection_description,
+        update_collection_refresh_outcome,
+    },
+    database_models::RefreshOutcomeDatabase,
+};
+
+// updates the fields that are set. An empty description removes the description
+pub fn set_collection_metadata(
+    id: &str,
+    description: Option<String>,
+    tags: Option<Vec<String>>,
+) -> Result<bool, DomainError> {
+    log_parameters_error(check_id_valid(id.to_string()))?;
+    if let Some(tags) = &tags {
+        log_parameters_error(check_collection_tags_valid(tags))?;
+    }
+    let collection_id = get_collection_id_by_deezer_id(id)?;
+    if let Some(description) = description {
+        let description = description.trim();
+        let description = (!description.is_empty()).then_some(description);
+        if let Err(e) = update_collection_description(id, description) {
+            return Err(log_database_error(&format!(
+                "Error while saving the description of collection {} : {:?}",
+                id, e
+            )));
+        }
+    }
+    if let Some(tags) = tags {
+        let mut tags = tags
+            .into_iter()
+            .map(|tag| tag.trim().to_string())
+            .collect::<Vec<_>>();
+        tags.sort();
+        tags.dedup();
+        if let Err(e) = set_collection_tags(&collection_id, &tags) {
+            return Err(log_database_error(&format!(
+                "Error while saving the tags of collection {} : {:?}",
+                id, e
+            )));
+        }
+    }
+    return Ok(true);
+}
+
+// returns the tags of every collection, by deezer id
+pub fn get_tags_by_collection() -> Result<HashMap<String, Vec<String>>, DomainError> {
+    match get_collections_tags() {
+        Ok(collections_tags) => {
+            let mut tags_by_collection: HashMap<String, Vec<String>> = HashMap::new();
+            for collection_tag in collections_tags.into_iter() {
+                tags_by_collection
+                    .entry(collection_tag.deezer_id)
+                    .or_default()
+                    .push(collection_tag.tag);
+            }
+            return Ok(tags_by_collection);
+        }
+        Err(e) => {
+            return Err(log_database_error(&format!(
+                "Error while getting the tags of the collections : {:?}",
+                e
+            )));
+        }
+    }
+}
+
+// a failure to save the outcome is only logged, so that it does not hide the result of the refresh
+pub fn record_refresh_success(id: &str, tracks_added: usize, track_count: usize) {
+    record_refresh_outcome(
+        id,
+        RefreshOutcomeDatabase {
+            refreshed_at: get_current_timestamp() as i64,
+            status: convert_refresh_status_to_string(&RefreshStatus::Success).to_string(),
+            error: None,
+            tracks_added: Some(tracks_added as i32),
+            track_count: Some(track_count as i32),
+        },
+    );
+}
+
+pub fn record_refresh_failure(id: &str, error: &str) {
+    record_refresh_outcome(
+        id,
+        RefreshOutcomeDatabase {
+            refreshed_at: get_current_timestamp() as i64,
+            status: convert_refresh_status_to_string(&RefreshStatus::Failed).to_string(),
+            error: Some(error.to_string()),
+            tracks_added: None,
+            track_count: None,
+        },
+    );
+}
+
+fn record_refresh_outcome(id: &str, outcome: RefreshOutcomeDatabase) {
+    if let Err(e) = update_collection_refresh_outcome(id, outcome) {
+        log_database_error(&format!(
+            "Error while saving the refresh outcome of collection {} : {:?}",
+            id, e
+        ));
+    }
+}
@@ -0,0 +1,97 @@
+use chrono::{DateTime, Datelike, Local, TimeZone};
+
+use super::{
+    collection_commons::{log_database_error, log_parameters_error},
+    controllers::check_id_valid,
+    converter::{
+        convert_refresh_schedule_to_string, convert_string_to_refresh_schedule,
+        REFRESH_STATUS_SUCCESS,
+    },
+    domain_models::RefreshSchedule,
+    errors::DomainError,
+};
+use crate::infrastructure::{
+    database::{get_collection, list_collections, update_collection_refresh_policy},
+    database_models::CollectionDatabase,
+};
+
+// updates the fields that are set, the other ones keep their current value
+pub fn set_refresh_policy(
+    id: &str,
+    enabled: Option<bool>,
+    schedule: Option<RefreshSchedule>,
+) -> Result<bool, DomainError> {
+    log_parameters_error(check_id_valid(id.to_string()))?;
+    let collection = match get_collection(id) {
+        Ok(collection) => collection,
+        Err(e) => {
+            return Err(log_database_error(&format!(
+                "Error while getting the refresh policy of collection {} : {:?}",
+                id, e
+            )));
+        }
+    };
+    let enabled = enabled.unwrap_or(collection.refresh_enabled);
+    let schedule = schedule
+        .unwrap_or_else(|| convert_string_to_refresh_schedule(&collection.refresh_schedule));
+    match update_collection_refresh_policy(
+        id,
+        &enabled,
+        convert_refresh_schedule_to_string(&schedule),
+    ) {
+        Ok(value) => return Ok(value),
+        Err(e) => {
+            return Err(log_database_error(&format!(
+                "Error while saving the refresh policy of collection {} : {:?}",
+                id, e
+            )));
+        }
+    }
+}
+
+// returns the collections that the refresh of all the collections must skip,
+// because they are disabled or they were already refreshed in the period of their schedule
+pub fn get_collections_not_due() -> Result<Vec<String>, DomainError> {
+    let now = Local::now();
+    match list_collections() {
+        Ok(collections) => {
+            return Ok(collections
+                .into_iter()
+                .filter(|collection| !is_refresh_due(collection, &now))
+                .map(|collection| collection.deezer_id)
+                .collect::<Vec<_>>());
+        }
+        Err(e) => {
+            return Err(log_database_error(&format!(
+                "Error while listing the collections to refresh : {:?}",
+                e
+            )));
+        }
+    }
+}
+
+// the periods are calendar days, weeks and months, so that a daily collection refreshed
+// by a nightly scheduler is not skipped when the runs are a few seconds apart
+fn is_refresh_due(collection: &CollectionDatabase, now: &DateTime<Local>) -> bool {
+    if !collection.refresh_enabled {
+        return false;
+    }
+    if collection.last_refresh_status.as_deref() != Some(REFRESH_STATUS_SUCCESS) {
+        return true;
+    }
+    let Some(last_refreshed_at) = collection
+        .last_refreshed_at
+        .and_then(|timestamp| Local.timestamp_opt(timestamp, 0).single())
+    else {
+        return true;
+    };
+    match convert_string_to_refresh_schedule(&collection.refresh_schedule) {
+        RefreshSchedule::Always => return true,
+        RefreshSchedule::Daily => return last_refreshed_at.date_naive() < now.date_naive(),
+        RefreshSchedule::Weekly => return last_refreshed_at.iso_week() < now.iso_week(),
+        RefreshSchedule::Monthly => {
+            return (last_refreshed_at.year(), last_refreshed_at.month())
+                < (now.year(), now.month())
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use std::collections::HashSet;
+
+use super::{
+    collection_commons::{
+        convert_string_to_u64, get_playlist, get_playlists, log_parameters_error,
+    },
+    collection_graph::{
+        get_ancestors, get_children, get_collection_graph, get_depth, get_descendants, get_parents,
+        is_in_cycle,
+    },
+    collection_management::{get_collection, get_direct_children_collections, list_collections},
+    controllers::check_id_valid,
+    domain_models::{ChildContribution, CollectionKind, CollectionStatistics, GlobalStatistics},
+    errors::DomainError,
+    track_filters::partition_tracks,
+};
+
+// computes the statistics of the collection from its playlist, the playlists of its children and the dependency graph
+pub async fn get_collection_statistics(id: &str) -> Result<CollectionStatistics, DomainError> {
+    log_parameters_error(check_id_valid(id.to_string()))?;
+    let collection = get_collection(id).await?;
+    let graph = get_collection_graph()?;
+    let playlist = get_playlist(&convert_string_to_u64(id)).await?;
+    let tracks_ids = playlist
+        .tracks
+        .iter()
+        .map(|track| track.deezer_id.clone())
+        .collect::<HashSet<_>>();
+    let mut inherited_tracks_ids: HashSet<String> = HashSet::new();
+    let mut children: Vec<ChildContribution> = Vec::new();
+    let children_collections = get_direct_children_collections(id)?;
+    let children_collections_ids = children_collections
+        .iter()
+        .map(|child| child.deezer_id.clone())
+        .collect::<Vec<_>>();
+    let children_playlists = get_playlists(&children_collections_ids).await?;
+    for (child, child_playlist) in children_collections.into_iter().zip(children_playlists) {
+        // the tracks filtered out of the child are not inherited from it
+        let (matching_tracks, _) = partition_tracks(&child.filter, child_playlist.tracks)?;
+        let child_tracks_ids = matching_tracks
+            .into_iter()
+            .map(|track| track.deezer_id)
+            .filter(|track_id| tracks_ids.contains(track_id))
+            .collect::<HashSet<_>>();
+        children.push(ChildContribution {
+            deezer_id: child.deezer_id,
+            name: child.name,
+            track_count: child_tracks_ids.len(),
+            share: get_share(child_tracks_ids.len(), tracks_ids.len()),
+        });
+        inherited_tracks_ids.extend(child_tracks_ids);
+    }
+    children.sort_by(|a, b| b.share.total_cmp(&a.share));
+    return Ok(CollectionStatistics {
+        deezer_id: collection.deezer_id,
+        name: collection.name,
+        track_count: tracks_ids.len(),
+        own_track_count: tracks_ids.len() - inherited_tracks_ids.len(),
+        inherited_track_count: inherited_tracks_ids.len(),
+        total_duration: playlist.tracks.iter().map(|track| track.duration).sum(),
+        direct_children_count: get_children(&graph, id).len(),
+        transitive_children_count: get_descendants(&graph, id)
+            .iter()
+            .filter(|el| *el != id)
+            .count(),
+        direct_parents_count: get_parents(&graph, id).len(),
+        transitive_parents_count: get_ancestors(&graph, id)
+            .iter()
+            .filter(|el| *el != id)
+            .count(),
+        depth: get_depth(&graph, id),
+        in_cycle: is_in_cycle(&graph, id),
+        children,
+    });
+}
+
+// computes the statistics of all the collections from the database only
+pub fn get_global_statistics() -> Result<GlobalStatistics, DomainError> {
+    let collections = list_collections()?;
+    let graph = get_collection_graph()?;
+    return Ok(GlobalStatistics {
+        collection_count: collections.len(),
+        smart_collection_count: collections
+            .iter()
+            .filter(|collection| collection.kind == CollectionKind::Smart)
+            .count(),
+        dependency_count: graph.children.values().map(Vec::len).sum(),
+        root_collection_count: graph
+            .collections
+            .iter()
+            .filter(|collection| get_parents(&graph, collection).is_empty())
+            .count(),
+        leaf_collection_count: graph
+            .collections
+            .iter()
+            .filter(|collection| get_children(&graph, collection).is_empty())
+            .count(),
+        collections_in_cycle_count: graph
+            .collections
+            .iter()
+            .filter(|collection| is_in_cycle(&graph, collection))
+            .count(),
+        max_depth: graph
+            .collections
+            .iter()
+            .filter_map(|collection| get_depth(&graph, collection))
+            .max()
+            .unwrap_or(0),
+        track_count: collections
+            .iter()
+            .filter_map(|collection| collection.metadata.track_count)
+            .sum(),
+    });
+}
+
+fn get_share(track_count: usize, total_track_count: usize) -> f64 {
+    if total_track_count == 0 {
+        return 0.0;
+    }
+    return track_count as f64 / total_track_count as f64;
+}
@@ -0,0 +1,104 @@
+use log::error;
+
+use super::{
+    collection_commons::{
+        convert_string_to_u64, get_playlist, log_database_error, log_deezer_error,
+        log_parameters_error,
+    },
+    collection_management::get_collection,
+    controllers::{check_collection_name_valid, check_id_valid},
+    domain_models::SyncedCollection,
+    errors::DomainError,
+    events::{publish_event, CollectionEvent},
+};
+use crate::infrastructure::{
+    database::{list_collections, update_collection_name_and_url},
+    deezer::update_playlist_title,
+};
+
+// renames the deezer playlist, then the collection
+pub async fn rename_collection(id: &str, name: &str) -> Result<bool, DomainError> {
+    log_parameters_error(check_id_valid(id.to_string()))?;
+    log_parameters_error(check_collection_name_valid(name))?;
+    let name = name.trim();
+    let collection = get_collection(id).await?;
+    // the collection is only renamed once deezer confirmed the new title, so that both stay the same
+    match update_playlist_title(id, name).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return Err(log_deezer_error(&format!(
+                "Deezer did not rename the playlist {} to {}",
+                id, name
+            )));
+        }
+        Err(e) => {
+            return Err(log_deezer_error(&format!(
+                "Error while renaming the playlist {} to {} : {:?}",
+                id, name, e
+            )));
+        }
+    }
+    save_collection_name_and_url(id, name, &collection.url)?;
+    publish_event(CollectionEvent::CollectionRenamed {
+        deezer_id: id.to_string(),
+        name: name.to_string(),
+    });
+    return Ok(true);
+}
+
+// pulls the current titles and urls of the playlists from deezer, and returns the collections that changed
+pub async fn sync_collections_from_deezer() -> Result<Vec<SyncedCollection>, DomainError> {
+    let collections = match list_collections() {
+        Ok(collections) => collections,
+        Err(e) => {
+            return Err(log_database_error(&format!(
+                "Error while listing the collections to sync : {:?}",
+                e
+            )));
+        }
+    };
+    let mut synced_collections: Vec<SyncedCollection> = Vec::new();
+    for collection in collections.into_iter() {
+        // a playlist that can not be read anymore should not prevent syncing the other ones
+        let playlist = match get_playlist(&convert_string_to_u64(&collection.deezer_id)).await {
+            Ok(playlist) => playlist,
+            Err(e) => {
+                error!(
+                    "Error while syncing collection {} : {:?}",
+                    collection.deezer_id, e
+                );
+                continue;
+            }
+        };
+        if playlist.title == collection.name && playlist.url == collection.url {
+            continue;
+        }
+        save_collection_name_and_url(&collection.deezer_id, &playlist.title, &playlist.url)?;
+        if playlist.title != collection.name {
+            publish_event(CollectionEvent::CollectionRenamed {
+                deezer_id: collection.deezer_id.clone(),
+                name: playlist.title.clone(),
+            });
+        }
+        synced_collections.push(SyncedCollection {
+            deezer_id: collection.deezer_id,
+            previous_name: collection.name,
+            name: playlist.title,
+            previous_url: collection.url,
+            url: playlist.url,
+        });
+    }
+    return Ok(synced_collections);
+}
+
+fn save_collection_name_and_url(id: &str, name: &str, url: &str) -> Result<bool, DomainError> {
+    match update_collection_name_and_url(id, name, url) {
+        Ok(value) => return Ok(value),
+        Err(e) => {
+            return Err(log_database_error(&format!(
+                "Error while saving the name and url of collection {} : {:?}",
+                id, e
+            )));
+        }
+    }
+}
@@ -0,0 +1,204 @@
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use log::{error, info, warn};
+use tokio::time::{interval, MissedTickBehavior};
+
+use super::{
+    collection_commons::{convert_string_to_u64, log_database_error},
+    collection_graph::{
+        get_ancestors, get_children, get_collection_graph, get_parents, CollectionGraph,
+    },
+    collection_management::refresh_collection,
+    errors::DomainError,
+};
+use crate::{
+    common::common::get_env_variable,
+    infrastructure::{
+        database::{list_collections, update_collection_checksum},
+        deezer::get_playlist_summary,
+        deezer_models::PlaylistSummaryResponse,
+    },
+};
+
+// Number of child playlists checked at each poll when WATCHER_BUDGET is not set
+const DEFAULT_WATCHER_BUDGET: u64 = 20;
+
+// position of the next child collection to check, so that every child is checked in turn
+static WATCHER_CURSOR: AtomicUsize = AtomicUsize::new(0);
+
+// polls the child playlists every WATCHER_INTERVAL seconds, if it is set
+pub fn start_collection_watcher() {
+    let watcher_interval = get_watcher_interval();
+    if watcher_interval == 0 {
+        info!("Watcher : disabled, WATCHER_INTERVAL is 0 or not set");
+        return;
+    }
+    info!(
+        "Watcher : checking {} child playlists every {} seconds",
+        get_watcher_budget(),
+        watcher_interval
+    );
+    tokio::spawn(async move {
+        let mut ticker = interval(Duration::from_secs(watcher_interval));
+        // a poll can take longer than the interval when the rate limiter kicks in
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = watch_child_collections().await {
+                error!(
+                    "Watcher : error while checking the child playlists : {:?}",
+                    e
+                );
+            }
+        }
+    });
+}
+
+// checks the checksum of the next child playlists, and refreshes the ancestors of the ones that changed.
+// Returns the refreshed collections
+pub async fn watch_child_collections() -> Result<Vec<String>, DomainError> {
+    let graph = get_collection_graph()?;
+    let collections = match list_collections() {
+        Ok(collections) => collections,
+        Err(e) => {
+            return Err(log_database_error(&format!(
+                "Error while listing the collections to watch : {:?}",
+                e
+            )));
+        }
+    };
+    // the disabled collections are never refreshed automatically
+    let disabled_ids = collections
+        .iter()
+        .filter(|collection| !collection.refresh_enabled)
+        .map(|collection| collection.deezer_id.clone())
+        .collect::<Vec<_>>();
+    let mut checksums: HashMap<String, Option<String>> = collections
+        .into_iter()
+        .map(|collection| (collection.deezer_id, collection.checksum))
+        .collect::<HashMap<_, _>>();
+    let child_ids = graph
+        .collections
+        .iter()
+        .filter(|id| !get_parents(&graph, id).is_empty())
+        .cloned()
+        .collect::<Vec<_>>();
+    if child_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let budget = (get_watcher_budget() as usize).min(child_ids.len());
+    let start = WATCHER_CURSOR.fetch_add(budget, Ordering::Relaxed) % child_ids.len();
+    let mut changed_ids: Vec<String> = Vec::new();
+    for id in child_ids.iter().cycle().skip(start).take(budget) {
+        let Some(checksum) = get_playlist_checksum(id).await else {
+            continue;
+        };
+        match checksums.get(id) {
+            Some(Some(previous_checksum)) if *previous_checksum == checksum => continue,
+            // the first checksum seen is only recorded, there is nothing to compare it to
+            Some(Some(_)) => changed_ids.push(id.clone()),
+            _ => {}
+        }
+        save_checksum(id, &checksum)?;
+        checksums.insert(id.clone(), Some(checksum));
+    }
+    let mut ancestor_ids: Vec<String> = Vec::new();
+    for id in changed_ids.iter() {
+        info!("Watcher : the tracks of collection {} changed", id);
+        for ancestor_id in get_ancestors(&graph, id).into_iter() {
+            if ancestor_id != *id
+                && !ancestor_ids.contains(&ancestor_id)
+                && !disabled_ids.contains(&ancestor_id)
+            {
+                ancestor_ids.push(ancestor_id);
+            }
+        }
+    }
+    let refreshed_ids = order_children_first(&graph, ancestor_ids);
+    for id in refreshed_ids.iter() {
+        if let Err(e) = refresh_collection(id).await {
+            error!(
+                "Watcher : error while refreshing collection {} : {:?}",
+                id, e
+            );
+            continue;
+        }
+        // the refresh changes the checksum of the collection, which must not trigger a refresh of its own ancestors
+        if !get_parents(&graph, id).is_empty() {
+            if let Some(checksum) = get_playlist_checksum(id).await {
+                save_checksum(id, &checksum)?;
+            }
+        }
+    }
+    return Ok(refreshed_ids);
+}
+
+// orders the collections so that each one is refreshed after its children,
+// the collections in a cycle being refreshed last
+fn order_children_first(graph: &CollectionGraph, ids: Vec<String>) -> Vec<String> {
+    let mut ordered_ids: Vec<String> = Vec::new();
+    let mut remaining_ids = ids;
+    loop {
+        let (ready_ids, blocked_ids): (Vec<String>, Vec<String>) =
+            remaining_ids.iter().cloned().partition(|id| {
+                get_children(graph, id)
+                    .iter()
+                    .all(|child_id| !remaining_ids.contains(child_id))
+            });
+        if ready_ids.is_empty() {
+            ordered_ids.extend(blocked_ids);
+            return ordered_ids;
+        }
+        ordered_ids.extend(ready_ids);
+        remaining_ids = blocked_ids;
+    }
+}
+
+async fn get_playlist_checksum(id: &str) -> Option<String> {
+    match get_playlist_summary(id).await {
+        Ok(PlaylistSummaryResponse::Playlist(playlist)) => return Some(playlist.checksum),
+        Ok(PlaylistSummaryResponse::Error(response)) => {
+            warn!(
+                "Watcher : playlist {} can not be read : {}",
+                id, response.error.message
+            );
+            return None;
+        }
+        Err(e) => {
+            warn!("Watcher : error while getting playlist {} : {:?}", id, e);
+            return None;
+        }
+    }
+}
+
+fn save_checksum(id: &str, checksum: &str) -> Result<bool, DomainError> {
+    match update_collection_checksum(id, checksum) {
+        Ok(updated) => return Ok(updated),
+        Err(e) => {
+            return Err(log_database_error(&format!(
+                "Error while saving the checksum of collection {} : {:?}",
+                id, e
+            )));
+        }
+    }
+}
+
+fn get_watcher_interval() -> u64 {
+    let watcher_interval = get_env_variable("WATCHER_INTERVAL");
+    if watcher_interval.is_empty() {
+        return 0;
+    }
+    return convert_string_to_u64(&watcher_interval);
+}
+
+fn get_watcher_budget() -> u64 {
+    let watcher_budget = get_env_variable("WATCHER_BUDGET");
+    if watcher_budget.is_empty() {
+        return DEFAULT_WATCHER_BUDGET;
+    }
+    return convert_string_to_u64(&watcher_budget);
+}
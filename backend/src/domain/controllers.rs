@@ -1,21 +1,23 @@
-use super::{collection_commons::get_playlist_id_from_url, domain_models::InitCollection};
+use regex::Regex;
 
-const API_PATH_PLAYLIST: &str = "https://www.deezer.com/fr/playlist/";
+use super::{
+    deezer_links::{parse_playlist_link, PlaylistLink},
+    domain_models::{
+        CollectionListQuery, Exclusion, ExclusionKind, InitCollection, OrderingPolicy,
+        SmartCollectionRule, TrackFilter, TrackOrder,
+    },
+};
 
 pub fn check_init_collections(params: &InitCollection) -> String {
     let mut res: String = "".to_owned();
-    match &params.from_playlist {
-        Some(playlist_url) => {
-            if !playlist_url.starts_with(API_PATH_PLAYLIST) {
-                res.push_str(
-                    "- from_playlist does not start with https://www.deezer.com/fr/playlist/\n",
-                );
-            }
-            if get_playlist_id_from_url(playlist_url.to_string()) == 0 {
-                res.push_str("- playlist id is invalid\n");
+    if let Some(playlist_url) = &params.from_playlist {
+        match parse_playlist_link(playlist_url) {
+            None => {
+                res.push_str("- from_playlist is not a deezer playlist url, short link or id\n")
             }
+            Some(PlaylistLink::Id(0)) => res.push_str("- playlist id is invalid\n"),
+            Some(_) => {}
         }
-        None => {}
     }
     return res;
 }
@@ -28,3 +30,81 @@ pub fn check_id_valid(id: String) -> String {
         }
     }
 }
+
+pub fn check_exclusion_valid(exclusion: &Exclusion) -> String {
+    let mut res: String = "".to_owned();
+    if exclusion.value.trim().is_empty() {
+        res.push_str("- exclusion value should not be empty\n");
+    }
+    if exclusion.kind == ExclusionKind::Track && exclusion.value.trim().parse::<u64>().is_err() {
+        res.push_str("- track exclusion value should be a deezer track id\n");
+    }
+    return res;
+}
+
+pub fn check_track_filter_valid(filter: &Option<TrackFilter>) -> String {
+    let mut res: String = "".to_owned();
+    if let Some(filter) = filter {
+        if let (Some(min), Some(max)) = (filter.min_duration, filter.max_duration) {
+            if min > max {
+                res.push_str("- min_duration should not be greater than max_duration\n");
+            }
+        }
+        if let Some(title_regex) = &filter.title_regex {
+            if Regex::new(title_regex).is_err() {
+                res.push_str("- title_regex is not a valid regular expression\n");
+            }
+        }
+    }
+    return res;
+}
+
+pub fn check_ordering_policy_valid(ordering: &OrderingPolicy) -> String {
+    let mut res: String = "".to_owned();
+    if ordering.sort_whole_playlist && ordering.track_order == TrackOrder::GroupedByChild {
+        res.push_str("- sort_whole_playlist needs a track_order other than grouped_by_child\n");
+    }
+    return res;
+}
+
+pub fn check_smart_collection_rule_valid(rule: &SmartCollectionRule) -> String {
+    let mut res: String = check_track_filter_valid(&rule.filter);
+    if let Some(source_collections) = &rule.source_collections {
+        for source_collection in source_collections.iter() {
+            if !check_id_valid(source_collection.to_string()).is_empty() {
+                res.push_str(&format!(
+                    "- source collection {} is not a valid id\n",
+                    source_collection
+                ));
+            }
+        }
+    }
+    return res;
+}
+
+pub fn check_collection_tags_valid(tags: &[String]) -> String {
+    let mut res: String = "".to_owned();
+    if tags.iter().any(|tag| tag.trim().is_empty()) {
+        res.push_str("- tags should not be empty\n");
+    }
+    return res;
+}
+
+pub fn check_collection_list_query(query: &CollectionListQuery) -> String {
+    let mut res: String = "".to_owned();
+    if query.limit.is_some_and(|limit| limit <= 0) {
+        res.push_str("- limit should be greater than 0\n");
+    }
+    if query.offset.is_some_and(|offset| offset < 0) {
+        res.push_str("- offset should not be negative\n");
+    }
+    return res;
+}
+
+pub fn check_collection_name_valid(name: &str) -> String {
+    let mut res: String = "".to_owned();
+    if name.trim().is_empty() {
+        res.push_str("- name should not be empty\n");
+    }
+    return res;
+}
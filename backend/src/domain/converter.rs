@@ -1,6 +1,16 @@
 use super::{
-    collection_commons::get_track_id_from_url,
-    domain_models::{Playlist, Track},
+    collection_commons::log_database_error,
+    deezer_links::{get_canonical_playlist_url, get_track_id_from_url},
+    domain_models::{
+        Collection, CollectionKind, CollectionListQuery, CollectionMetadata, CollectionOrigin,
+        CollectionSort, OrderingPolicy, Playlist, RefreshPolicy, RefreshRun, RefreshRunStatus,
+        RefreshSchedule, RefreshStatus, SmartCollectionRule, SortDirection, Track, TrackFilter,
+        TrackOrder,
+    },
+    errors::DomainError,
+};
+use crate::infrastructure::database_models::{
+    CollectionDatabase, CollectionListFilterDatabase, CollectionSortDatabase, RefreshRunDatabase,
 };
 use deezer::models::{DeezerArray, PlaylistTrack};
 
@@ -10,7 +20,8 @@ pub fn convert_playlist(playlist: deezer::models::Playlist) -> Playlist {
         title: playlist.title,
         public: playlist.is_public,
         nb_tracks: playlist.nb_tracks,
-        url: playlist.link,
+        url: get_canonical_playlist_url(&playlist.id),
+        creator_id: playlist.creator.id,
         tracks: convert_tracks(playlist.tracks),
     };
 }
@@ -19,7 +30,7 @@ fn convert_tracks(tracks: DeezerArray<PlaylistTrack>) -> Vec<Track> {
     tracks
         .data
         .into_iter()
-        .map(|track| convert_track(track))
+        .map(convert_track)
         .collect::<Vec<_>>()
 }
 
@@ -29,6 +40,240 @@ fn convert_track(track: PlaylistTrack) -> Track {
         title: track.title,
         link: track.link.clone(),
         artist: track.artist.name,
+        artist_id: track.artist.id,
+        duration: track.duration_in_seconds,
+        explicit: track.has_explicit_lyrics,
+        time_add: track.added_on,
         deezer_id: format!("{}", get_track_id_from_url(track.link)),
     };
 }
+
+pub const COLLECTION_KIND_STANDARD: &str = "standard";
+pub const COLLECTION_KIND_SMART: &str = "smart";
+
+pub fn convert_collection(collection: CollectionDatabase, tags: Vec<String>) -> Collection {
+    let metadata = convert_collection_metadata(&collection, tags);
+    return Collection {
+        name: collection.name,
+        deezer_id: collection.deezer_id,
+        url: collection.url,
+        kind: convert_string_to_collection_kind(&collection.kind),
+        rule: convert_string_to_smart_collection_rule(collection.rule),
+        ordering: OrderingPolicy {
+            track_order: convert_string_to_track_order(&collection.track_order),
+            sort_whole_playlist: collection.sort_whole_playlist,
+        },
+        refresh_policy: RefreshPolicy {
+            enabled: collection.refresh_enabled,
+            schedule: convert_string_to_refresh_schedule(&collection.refresh_schedule),
+        },
+        metadata,
+        origin: convert_string_to_collection_origin(&collection.origin),
+        owner_id: collection.owner_id,
+    };
+}
+
+pub const COLLECTION_ORIGIN_CREATED: &str = "created";
+pub const COLLECTION_ORIGIN_IMPORTED: &str = "imported";
+
+pub fn convert_collection_origin_to_string(origin: &CollectionOrigin) -> &'static str {
+    match origin {
+        CollectionOrigin::Created => return COLLECTION_ORIGIN_CREATED,
+        CollectionOrigin::Imported => return COLLECTION_ORIGIN_IMPORTED,
+    }
+}
+
+pub fn convert_string_to_collection_origin(origin: &str) -> CollectionOrigin {
+    match origin {
+        COLLECTION_ORIGIN_CREATED => return CollectionOrigin::Created,
+        _ => return CollectionOrigin::Imported,
+    }
+}
+
+pub fn convert_collection_metadata(
+    collection: &CollectionDatabase,
+    tags: Vec<String>,
+) -> CollectionMetadata {
+    return CollectionMetadata {
+        description: collection.description.clone(),
+        tags,
+        created_at: collection.created_at.map(|created_at| created_at as u64),
+        last_refreshed_at: collection
+            .last_refreshed_at
+            .map(|last_refreshed_at| last_refreshed_at as u64),
+        last_refresh_status: collection
+            .last_refresh_status
+            .as_deref()
+            .map(convert_string_to_refresh_status),
+        last_refresh_error: collection.last_refresh_error.clone(),
+        last_refresh_tracks_added: collection
+            .last_refresh_tracks_added
+            .map(|tracks_added| tracks_added as u64),
+        track_count: collection.track_count.map(|track_count| track_count as u64),
+    };
+}
+
+pub const REFRESH_STATUS_SUCCESS: &str = "success";
+pub const REFRESH_STATUS_FAILED: &str = "failed";
+
+pub fn convert_refresh_status_to_string(status: &RefreshStatus) -> &'static str {
+    match status {
+        RefreshStatus::Success => return REFRESH_STATUS_SUCCESS,
+        RefreshStatus::Failed => return REFRESH_STATUS_FAILED,
+    }
+}
+
+pub fn convert_string_to_refresh_status(status: &str) -> RefreshStatus {
+    match status {
+        REFRESH_STATUS_SUCCESS => return RefreshStatus::Success,
+        _ => return RefreshStatus::Failed,
+    }
+}
+
+pub const REFRESH_RUN_STATUS_RUNNING: &str = "running";
+pub const REFRESH_RUN_STATUS_SUCCESS: &str = "success";
+pub const REFRESH_RUN_STATUS_FAILED: &str = "failed";
+pub const REFRESH_RUN_STATUS_SKIPPED: &str = "skipped";
+
+pub fn convert_refresh_run_status_to_string(status: &RefreshRunStatus) -> &'static str {
+    match status {
+        RefreshRunStatus::Running => return REFRESH_RUN_STATUS_RUNNING,
+        RefreshRunStatus::Success => return REFRESH_RUN_STATUS_SUCCESS,
+        RefreshRunStatus::Failed => return REFRESH_RUN_STATUS_FAILED,
+        RefreshRunStatus::Skipped => return REFRESH_RUN_STATUS_SKIPPED,
+    }
+}
+
+pub fn convert_string_to_refresh_run_status(status: &str) -> RefreshRunStatus {
+    match status {
+        REFRESH_RUN_STATUS_RUNNING => return RefreshRunStatus::Running,
+        REFRESH_RUN_STATUS_SUCCESS => return RefreshRunStatus::Success,
+        REFRESH_RUN_STATUS_SKIPPED => return RefreshRunStatus::Skipped,
+        _ => return RefreshRunStatus::Failed,
+    }
+}
+
+pub fn convert_refresh_run(run: RefreshRunDatabase) -> RefreshRun {
+    return RefreshRun {
+        id: run.id as u64,
+        started_at: run.started_at as u64,
+        finished_at: run.finished_at.map(|finished_at| finished_at as u64),
+        status: convert_string_to_refresh_run_status(&run.status),
+        error: run.error,
+    };
+}
+
+pub const REFRESH_SCHEDULE_ALWAYS: &str = "always";
+pub const REFRESH_SCHEDULE_DAILY: &str = "daily";
+pub const REFRESH_SCHEDULE_WEEKLY: &str = "weekly";
+pub const REFRESH_SCHEDULE_MONTHLY: &str = "monthly";
+
+pub fn convert_refresh_schedule_to_string(schedule: &RefreshSchedule) -> &'static str {
+    match schedule {
+        RefreshSchedule::Always => return REFRESH_SCHEDULE_ALWAYS,
+        RefreshSchedule::Daily => return REFRESH_SCHEDULE_DAILY,
+        RefreshSchedule::Weekly => return REFRESH_SCHEDULE_WEEKLY,
+        RefreshSchedule::Monthly => return REFRESH_SCHEDULE_MONTHLY,
+    }
+}
+
+pub fn convert_string_to_refresh_schedule(schedule: &str) -> RefreshSchedule {
+    match schedule {
+        REFRESH_SCHEDULE_DAILY => return RefreshSchedule::Daily,
+        REFRESH_SCHEDULE_WEEKLY => return RefreshSchedule::Weekly,
+        REFRESH_SCHEDULE_MONTHLY => return RefreshSchedule::Monthly,
+        _ => return RefreshSchedule::Always,
+    }
+}
+
+pub const TRACK_ORDER_GROUPED_BY_CHILD: &str = "grouped_by_child";
+pub const TRACK_ORDER_TIME_ADD: &str = "time_add";
+pub const TRACK_ORDER_ARTIST: &str = "artist";
+pub const TRACK_ORDER_TITLE: &str = "title";
+
+pub fn convert_track_order_to_string(track_order: &TrackOrder) -> &'static str {
+    match track_order {
+        TrackOrder::GroupedByChild => return TRACK_ORDER_GROUPED_BY_CHILD,
+        TrackOrder::TimeAdd => return TRACK_ORDER_TIME_ADD,
+        TrackOrder::Artist => return TRACK_ORDER_ARTIST,
+        TrackOrder::Title => return TRACK_ORDER_TITLE,
+    }
+}
+
+pub fn convert_string_to_track_order(track_order: &str) -> TrackOrder {
+    match track_order {
+        TRACK_ORDER_TIME_ADD => return TrackOrder::TimeAdd,
+        TRACK_ORDER_ARTIST => return TrackOrder::Artist,
+        TRACK_ORDER_TITLE => return TrackOrder::Title,
+        _ => return TrackOrder::GroupedByChild,
+    }
+}
+
+pub fn convert_collection_kind_to_string(kind: &CollectionKind) -> &'static str {
+    match kind {
+        CollectionKind::Standard => return COLLECTION_KIND_STANDARD,
+        CollectionKind::Smart => return COLLECTION_KIND_SMART,
+    }
+}
+
+pub fn convert_string_to_collection_kind(kind: &str) -> CollectionKind {
+    match kind {
+        COLLECTION_KIND_SMART => return CollectionKind::Smart,
+        _ => return CollectionKind::Standard,
+    }
+}
+
+pub fn convert_smart_collection_rule_to_string(rule: &SmartCollectionRule) -> Option<String> {
+    return serde_json::to_string(rule).ok();
+}
+
+fn convert_string_to_smart_collection_rule(rule: Option<String>) -> Option<SmartCollectionRule> {
+    return rule.and_then(
+        |rule| match serde_json::from_str::<SmartCollectionRule>(&rule) {
+            Ok(rule) => Some(rule),
+            Err(e) => {
+                log_database_error(&format!("Error while reading rule {} : {:?}", rule, e));
+                None
+            }
+        },
+    );
+}
+
+// a stored filter that can not be read fails, instead of letting all the tracks of the child through
+pub fn convert_string_to_track_filter(
+    filter: Option<String>,
+) -> Result<Option<TrackFilter>, DomainError> {
+    match filter {
+        Some(filter) => match serde_json::from_str::<TrackFilter>(&filter) {
+            Ok(filter) => return Ok(Some(filter)),
+            Err(e) => {
+                return Err(log_database_error(&format!(
+                    "Error while reading filter {} : {:?}",
+                    filter, e
+                )));
+            }
+        },
+        None => return Ok(None),
+    }
+}
+
+pub fn convert_track_filter_to_string(filter: &Option<TrackFilter>) -> Option<String> {
+    return filter
+        .as_ref()
+        .and_then(|filter| serde_json::to_string(filter).ok());
+}
+
+pub fn convert_collection_list_query(query: &CollectionListQuery) -> CollectionListFilterDatabase {
+    return CollectionListFilterDatabase {
+        tag: query.tag.clone(),
+        search: query.search.clone(),
+        sort: query.sort.as_ref().map(|sort| match sort {
+            CollectionSort::Name => CollectionSortDatabase::Name,
+            CollectionSort::CreatedAt => CollectionSortDatabase::CreatedAt,
+            CollectionSort::LastRefreshedAt => CollectionSortDatabase::LastRefreshedAt,
+        }),
+        descending: query.direction == SortDirection::Desc,
+        limit: query.limit,
+        offset: query.offset,
+    };
+}
@@ -0,0 +1,118 @@
+use std::sync::OnceLock;
+
+use log::error;
+use regex::Regex;
+
+use super::{
+    collection_commons::{convert_string_to_u64, log_deezer_error},
+    errors::DomainError,
+};
+use crate::infrastructure::deezer::resolve_short_link;
+
+const CANONICAL_PLAYLIST_URL: &str = "https://www.deezer.com/playlist/";
+
+// e.g. https://www.deezer.com/fr/playlist/123?utm_source=x, deezer.com/en-gb/playlist/123/ or deezer://www.deezer.com/playlist/123
+const PLAYLIST_LINK_PATTERN: &str = concat!(
+    r"(?i)^(?:(?:https?://)?(?:www\.)?deezer\.com(?:/[a-z]{2}(?:[-_][a-z]{2})?)?/",
+    r"|deezer://(?:www\.deezer\.com/)?)",
+    r"playlist/(\d+)/?(?:[?#].*)?$"
+);
+// e.g. https://www.deezer.com/track/123, deezer.com/fr/track/123?autoplay=true or deezer://www.deezer.com/track/123
+const TRACK_LINK_PATTERN: &str = concat!(
+    r"(?i)^(?:(?:https?://)?(?:www\.)?deezer\.com(?:/[a-z]{2}(?:[-_][a-z]{2})?)?/",
+    r"|deezer://(?:www\.deezer\.com/)?)",
+    r"track/(\d+)/?(?:[?#].*)?$"
+);
+// e.g. https://deezer.page.link/AbCd1234
+const SHORT_LINK_PATTERN: &str =
+    r"(?i)^(?:https?://)?deezer\.page\.link/([a-z0-9_-]+)/?(?:[?#].*)?$";
+
+static PLAYLIST_LINK_REGEX: OnceLock<Regex> = OnceLock::new();
+static SHORT_LINK_REGEX: OnceLock<Regex> = OnceLock::new();
+static TRACK_LINK_REGEX: OnceLock<Regex> = OnceLock::new();
+static LAST_PATH_ID_REGEX: OnceLock<Regex> = OnceLock::new();
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlaylistLink {
+    Id(u64),
+    ShortLink(String), // path of the short link, that has to be resolved to get the playlist id
+}
+
+// parses a playlist url in any locale, a deezer:// uri, a short link or a bare id
+pub fn parse_playlist_link(link: &str) -> Option<PlaylistLink> {
+    let playlist_link_regex =
+        PLAYLIST_LINK_REGEX.get_or_init(|| Regex::new(PLAYLIST_LINK_PATTERN).unwrap());
+    let short_link_regex = SHORT_LINK_REGEX.get_or_init(|| Regex::new(SHORT_LINK_PATTERN).unwrap());
+    let link = link.trim();
+    if let Some(captures) = short_link_regex.captures(link) {
+        return Some(PlaylistLink::ShortLink(captures[1].to_string()));
+    }
+    let id = match playlist_link_regex.captures(link) {
+        Some(captures) => captures[1].to_string(),
+        None => link.to_string(),
+    };
+    match id.parse::<u64>() {
+        Ok(id) => return Some(PlaylistLink::Id(id)),
+        Err(_) => return None,
+    }
+}
+
+// returns the id of the playlist, expanding the short links
+pub async fn resolve_playlist_id(link: &str) -> Result<u64, DomainError> {
+    match parse_playlist_link(link) {
+        Some(PlaylistLink::Id(id)) => return Ok(id),
+        Some(PlaylistLink::ShortLink(path)) => {
+            let location = match resolve_short_link(&path).await {
+                Ok(location) => location,
+                Err(e) => {
+                    return Err(log_deezer_error(&format!(
+                        "Error while resolving the short link {} : {:?}",
+                        link, e
+                    )));
+                }
+            };
+            // the short link must lead to a playlist, and not to another short link
+            match location.as_deref().map(parse_playlist_link) {
+                Some(Some(PlaylistLink::Id(id))) => return Ok(id),
+                _ => {
+                    let message = format!(
+                        "the short link {} does not lead to a playlist ({:?})",
+                        link, location
+                    );
+                    error!("Handler - error in input parameters : {}", message);
+                    return Err(DomainError::DomainParamError(message));
+                }
+            }
+        }
+        None => {
+            let message = format!("{} is not a deezer playlist", link);
+            error!("Handler - error in input parameters : {}", message);
+            return Err(DomainError::DomainParamError(message));
+        }
+    }
+}
+
+// returns the id of the track if the link is a deezer track url in any locale or a deezer:// uri
+pub fn parse_track_link(link: &str) -> Option<u64> {
+    let track_link_regex = TRACK_LINK_REGEX.get_or_init(|| Regex::new(TRACK_LINK_PATTERN).unwrap());
+    return track_link_regex
+        .captures(link.trim())
+        .and_then(|captures| captures[1].parse::<u64>().ok());
+}
+
+pub fn get_canonical_playlist_url(id: &u64) -> String {
+    return format!("{}{}", CANONICAL_PLAYLIST_URL, id);
+}
+
+// returns the id at the end of a deezer link, ignoring the query string and trailing slash
+pub fn get_track_id_from_url(url: String) -> u64 {
+    let last_path_id_regex =
+        LAST_PATH_ID_REGEX.get_or_init(|| Regex::new(r"/(\d+)/?(?:[?#].*)?$").unwrap());
+    match last_path_id_regex.captures(&url) {
+        Some(captures) => return convert_string_to_u64(&captures[1]),
+        None => {
+            error!("Handler : Error while parsing url {}", url);
+            return 0;
+        }
+    }
+}
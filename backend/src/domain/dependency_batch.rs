@@ -0,0 +1,257 @@
+use log::error;
+
+use super::{
+    collection_commons::log_database_error,
+    collection_graph::{
+        build_collection_graph, get_children, is_cycle_dependency, CollectionGraph,
+    },
+    collection_management::refresh_collection,
+    controllers::{check_id_valid, check_track_filter_valid},
+    converter::convert_track_filter_to_string,
+    domain_models::{
+        DependencyAction, DependencyBatchReport, DependencyOperation, DependencyOperationResult,
+        DependencyOperationStatus, MoveCollectionReport,
+    },
+    errors::DomainError,
+    events::{publish_event, CollectionEvent},
+};
+use crate::infrastructure::{
+    database::apply_planned_dependency_mutations,
+    database_models::{CollectionDependencyDatabase, DependencyMutationDatabase},
+};
+
+// validates every operation against the state left by the previous ones, then applies all of them in one transaction
+// the graph is read in the same transaction, and nothing is applied if one of the operations is invalid
+pub fn apply_dependency_batch(
+    operations: Vec<DependencyOperation>,
+    allow_cycles: bool,
+) -> Result<DependencyBatchReport, DomainError> {
+    let mut report = DependencyBatchReport::default();
+    let plan = |collections, dependencies| {
+        let graph = build_collection_graph(collections, dependencies);
+        return plan_dependency_batch(graph, &operations, allow_cycles, &mut report);
+    };
+    match apply_planned_dependency_mutations(plan) {
+        Ok(true) => {}
+        Ok(false) => return Ok(report),
+        Err(e) => {
+            return Err(log_database_error(&format!(
+                "Error while applying the batch of dependency operations : {:?}",
+                e
+            )));
+        }
+    }
+    publish_dependency_events(operations);
+    for result in report.results.iter_mut() {
+        result.status = DependencyOperationStatus::Applied;
+    }
+    report.applied = true;
+    return Ok(report);
+}
+
+// moves the child from its old parent to its new parent in one transaction, keeping the filter of the dependency
+// the new parent can then be refreshed, the move being kept if the refresh fails
+pub async fn move_collection(
+    child_id: &str,
+    old_parent_id: &str,
+    new_parent_id: &str,
+    refresh: bool,
+) -> Result<MoveCollectionReport, DomainError> {
+    let operations = vec![
+        DependencyOperation {
+            action: DependencyAction::Remove,
+            parent_id: old_parent_id.to_string(),
+            child_id: child_id.to_string(),
+            filter: None,
+        },
+        DependencyOperation {
+            action: DependencyAction::Add,
+            parent_id: new_parent_id.to_string(),
+            child_id: child_id.to_string(),
+            filter: None,
+        },
+    ];
+    let mut report = DependencyBatchReport::default();
+    let plan = |collections, dependencies: Vec<CollectionDependencyDatabase>| {
+        // the filter is read in the transaction, so that it is the one of the removed dependency
+        let filter = dependencies
+            .iter()
+            .find(|dependency| {
+                dependency.parent_deezer_id == old_parent_id
+                    && dependency.child_deezer_id == child_id
+            })
+            .and_then(|dependency| dependency.filter.clone());
+        let graph = build_collection_graph(collections, dependencies);
+        let mut mutations = plan_dependency_batch(graph, &operations, false, &mut report)?;
+        for mutation in mutations.iter_mut() {
+            if let DependencyMutationDatabase::Add {
+                filter: added_filter,
+                ..
+            } = mutation
+            {
+                added_filter.clone_from(&filter);
+            }
+        }
+        return Some(mutations);
+    };
+    match apply_planned_dependency_mutations(plan) {
+        Ok(true) => {}
+        Ok(false) => {
+            let failures = report
+                .results
+                .into_iter()
+                .filter_map(|result| result.error)
+                .collect::<String>();
+            error!("Handler - error in input parameters : {}", failures);
+            return Err(DomainError::DomainParamError(failures));
+        }
+        Err(e) => {
+            return Err(log_database_error(&format!(
+                "Error while moving collection {} : {:?}",
+                child_id, e
+            )));
+        }
+    }
+    publish_dependency_events(operations);
+    let mut move_report = MoveCollectionReport {
+        moved: true,
+        refresh_error: None,
+    };
+    // the move is already committed, so a failing refresh is only reported
+    if refresh {
+        if let Err(e) = refresh_collection(new_parent_id).await {
+            move_report.refresh_error = Some(e.to_string());
+        }
+    }
+    return Ok(move_report);
+}
+
+// checks the operations in order against the graph, filling the report, and returns the mutations to apply
+// or None if one of the operations is invalid
+fn plan_dependency_batch(
+    mut graph: CollectionGraph,
+    operations: &[DependencyOperation],
+    allow_cycles: bool,
+    report: &mut DependencyBatchReport,
+) -> Option<Vec<DependencyMutationDatabase>> {
+    for (index, operation) in operations.iter().enumerate() {
+        let failures = check_dependency_operation(&graph, operation, allow_cycles);
+        if failures.is_empty() {
+            apply_to_graph(&mut graph, operation);
+            report.results.push(DependencyOperationResult {
+                index,
+                status: DependencyOperationStatus::NotApplied,
+                error: None,
+            });
+        } else {
+            report.results.push(DependencyOperationResult {
+                index,
+                status: DependencyOperationStatus::Invalid,
+                error: Some(failures),
+            });
+        }
+    }
+    if report
+        .results
+        .iter()
+        .any(|result| result.status == DependencyOperationStatus::Invalid)
+    {
+        return None;
+    }
+    return Some(
+        operations
+            .iter()
+            .map(|operation| match operation.action {
+                DependencyAction::Add => DependencyMutationDatabase::Add {
+                    parent_deezer_id: operation.parent_id.clone(),
+                    child_deezer_id: operation.child_id.clone(),
+                    filter: convert_track_filter_to_string(&operation.filter),
+                },
+                DependencyAction::Remove => DependencyMutationDatabase::Remove {
+                    parent_deezer_id: operation.parent_id.clone(),
+                    child_deezer_id: operation.child_id.clone(),
+                },
+            })
+            .collect::<Vec<_>>(),
+    );
+}
+
+fn publish_dependency_events(operations: Vec<DependencyOperation>) {
+    for operation in operations.into_iter() {
+        publish_event(match operation.action {
+            DependencyAction::Add => CollectionEvent::DependencyAdded {
+                parent_id: operation.parent_id,
+                child_id: operation.child_id,
+            },
+            DependencyAction::Remove => CollectionEvent::DependencyRemoved {
+                parent_id: operation.parent_id,
+                child_id: operation.child_id,
+            },
+        });
+    }
+}
+
+// returns the reasons why the operation can not be applied, empty if it can
+fn check_dependency_operation(
+    graph: &CollectionGraph,
+    operation: &DependencyOperation,
+    allow_cycles: bool,
+) -> String {
+    let mut res: String = "".to_owned();
+    for id in [&operation.parent_id, &operation.child_id] {
+        if !check_id_valid(id.clone()).is_empty() {
+            res.push_str(&format!("- {} is not a valid collection id\n", id));
+        } else if !graph.collections.contains(id) {
+            res.push_str(&format!("- collection {} does not exist\n", id));
+        }
+    }
+    if !res.is_empty() {
+        return res;
+    }
+    let is_child = get_children(graph, &operation.parent_id).contains(&operation.child_id);
+    match operation.action {
+        DependencyAction::Add => {
+            res.push_str(&check_track_filter_valid(&operation.filter));
+            if is_child {
+                res.push_str(&format!(
+                    "- collection {} already is a child of collection {}\n",
+                    operation.child_id, operation.parent_id
+                ));
+            } else if !allow_cycles
+                && is_cycle_dependency(graph, &operation.parent_id, &operation.child_id)
+            {
+                res.push_str(&format!(
+                    "- adding collection {} to collection {} would create a cycle\n",
+                    operation.child_id, operation.parent_id
+                ));
+            }
+        }
+        DependencyAction::Remove => {
+            if !is_child {
+                res.push_str(&format!(
+                    "- collection {} is not a child of collection {}\n",
+                    operation.child_id, operation.parent_id
+                ));
+            }
+        }
+    }
+    return res;
+}
+
+fn apply_to_graph(graph: &mut CollectionGraph, operation: &DependencyOperation) {
+    let children = graph
+        .children
+        .entry(operation.parent_id.clone())
+        .or_default();
+    let parents = graph.parents.entry(operation.child_id.clone()).or_default();
+    match operation.action {
+        DependencyAction::Add => {
+            children.push(operation.child_id.clone());
+            parents.push(operation.parent_id.clone());
+        }
+        DependencyAction::Remove => {
+            children.retain(|child_id| *child_id != operation.child_id);
+            parents.retain(|parent_id| *parent_id != operation.parent_id);
+        }
+    }
+}
@@ -11,6 +11,18 @@ pub struct CollectionListElement {
     pub name: String,
     pub deezer_id: String,
     pub url: String,
+    pub kind: CollectionKind,
+    pub metadata: CollectionMetadata,
+}
+
+// a collection whose name or url changed in deezer
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct SyncedCollection {
+    pub deezer_id: String,
+    pub previous_name: String,
+    pub name: String,
+    pub previous_url: String,
+    pub url: String,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -20,6 +32,7 @@ pub struct Playlist {
     pub public: bool,
     pub nb_tracks: u64,
     pub url: String,
+    pub creator_id: u64,
     pub tracks: Vec<Track>,
 }
 
@@ -30,6 +43,10 @@ pub struct Track {
     pub title: String,
     pub link: String,
     pub artist: String,
+    pub artist_id: u64,
+    pub duration: u64, // in seconds
+    pub explicit: bool,
+    pub time_add: u64, // timestamp of the addition of the track in the playlist
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -37,4 +54,460 @@ pub struct Collection {
     pub name: String,
     pub deezer_id: String,
     pub url: String,
+    pub kind: CollectionKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rule: Option<SmartCollectionRule>,
+    pub ordering: OrderingPolicy,
+    pub refresh_policy: RefreshPolicy,
+    pub metadata: CollectionMetadata,
+    pub origin: CollectionOrigin,
+    pub owner_id: Option<String>, // deezer user id, not known for the collections saved before it was recorded
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CollectionOrigin {
+    Created,  // the deezer playlist was created by this app
+    Imported, // the deezer playlist already existed
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct CollectionMetadata {
+    pub description: Option<String>,
+    pub tags: Vec<String>,
+    pub created_at: Option<u64>, // timestamp, not set for the collections created before it was saved
+    pub last_refreshed_at: Option<u64>, // timestamp of the last refresh, successful or not
+    pub last_refresh_status: Option<RefreshStatus>,
+    pub last_refresh_error: Option<String>,
+    pub last_refresh_tracks_added: Option<u64>,
+    pub track_count: Option<u64>, // number of tracks of the deezer playlist at the creation or the last successful refresh
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RefreshStatus {
+    Success,
+    Failed,
+}
+
+// how the tracks inherited from the children are ordered in the collection
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct OrderingPolicy {
+    pub track_order: TrackOrder,
+    pub sort_whole_playlist: bool, // sorts every track of the collection, not only the added ones
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TrackOrder {
+    #[default]
+    GroupedByChild, // the added tracks keep the order of their child, children one after the other
+    TimeAdd, // by date of addition in the child
+    Artist,
+    Title,
+}
+
+// when the collection is refreshed by the refresh of all the collections
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct RefreshPolicy {
+    pub enabled: bool, // a disabled collection is only refreshed on demand
+    pub schedule: RefreshSchedule,
+}
+
+// the collection is refreshed at most once per day, week or month, unless its last refresh failed
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RefreshSchedule {
+    #[default]
+    Always, // on every refresh of all the collections
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CollectionSort {
+    Name,
+    CreatedAt,
+    LastRefreshedAt,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SortDirection {
+    #[default]
+    Asc,
+    Desc,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct CollectionListQuery {
+    pub tag: Option<String>,
+    pub search: Option<String>, // substring of the name, case insensitive
+    pub sort: Option<CollectionSort>, // in order of creation if not set
+    pub direction: SortDirection,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+// a page of the collections, and the number of collections matching the query in all the pages
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct CollectionListPage {
+    pub collections: Vec<CollectionListElement>,
+    pub total_count: i64,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CollectionKind {
+    Standard, // tracks come from the children collections
+    Smart,    // tracks come from a rule over the tracks of the other collections
+}
+
+// query over the tracks of the other collections, materialized in a smart collection
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct SmartCollectionRule {
+    pub source_collections: Option<Vec<String>>, // deezer playlist ids, every other collection if not set
+    pub filter: Option<TrackFilter>,
+    pub added_within_days: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ChildCollection {
+    pub name: String,
+    pub deezer_id: String,
+    pub url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter: Option<TrackFilter>,
+}
+
+// filter applied on the tracks of a child collection. Every set condition has to match
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct TrackFilter {
+    pub artists_in: Option<Vec<String>>, // deezer artist ids or names
+    pub artists_not_in: Option<Vec<String>>, // deezer artist ids or names
+    pub exclude_explicit: Option<bool>,
+    pub min_duration: Option<u64>, // in seconds
+    pub max_duration: Option<u64>, // in seconds
+    pub title_regex: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExclusionKind {
+    Track,  // deezer track id
+    Artist, // deezer artist id or artist name
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Exclusion {
+    pub kind: ExclusionKind,
+    pub value: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SkipReason {
+    Excluded {
+        exclusion: Exclusion,
+    },
+    Filtered {
+        child_id: String,
+    },
+    Duplicate {
+        duplicate_of: String, // deezer id of the equivalent track
+        matched_by: DuplicateMatch,
+    },
+}
+
+// how two tracks with different deezer ids were found to be the same recording
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateMatch {
+    Isrc,
+    TitleAndArtist, // normalized, ignoring remaster or featuring mentions
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct DuplicateGroup {
+    pub tracks: Vec<Track>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct SkippedTrack {
+    pub track: Track,
+    pub reason: SkipReason,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct RefreshReport {
+    pub deezer_id: String,
+    pub tracks_to_add: Vec<Track>,
+    pub skipped_tracks: Vec<SkippedTrack>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HealthIssue {
+    Deleted, // the deezer playlist does not exist anymore
+    Unreadable {
+        error: String,
+    }, // deezer refuses to return the playlist, e.g because it became private
+    Private, // the playlist of another user is not public anymore
+    OwnerChanged {
+        previous_owner_id: String,
+        owner_id: String,
+    },
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct CollectionHealthIssue {
+    pub deezer_id: String,
+    pub name: String,
+    pub issue: HealthIssue,
+}
+
+// a dependency whose parent or child collection is missing in the database
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct DanglingDependency {
+    pub dependency_id: i32,
+    pub parent_id: Option<String>, // deezer playlist id, not set if the parent is missing
+    pub child_id: Option<String>,  // deezer playlist id, not set if the child is missing
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HealthAction {
+    CollectionPruned { deezer_id: String },
+    DependencyPruned { dependency_id: i32 },
+    OwnerUpdated { deezer_id: String, owner_id: String },
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct HealthReport {
+    pub collections: Vec<CollectionHealthIssue>,
+    pub dangling_dependencies: Vec<DanglingDependency>,
+    pub actions: Vec<HealthAction>, // what was done to prune or repair the issues
+}
+
+// a playlist of the user, and whether it is already a collection
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct UserPlaylist {
+    pub deezer_id: String,
+    pub name: String,
+    pub url: String,
+    pub already_imported: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct FailedImport {
+    pub deezer_id: String,
+    pub error: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct ImportReport {
+    pub imported: Vec<UserPlaylist>, // playlists registered as new collections
+    pub already_existing: Vec<UserPlaylist>, // playlists that were already collections
+    pub not_found: Vec<String>,      // selected ids that are not playlists of the user
+    pub failed: Vec<FailedImport>,
+}
+
+// the tracks of a collection that are also in one of its children
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ChildContribution {
+    pub deezer_id: String,
+    pub name: String,
+    pub track_count: usize,
+    pub share: f64, // of the tracks of the collection, between 0 and 1. The shares can add up to more than 1 if the children share tracks
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct CollectionStatistics {
+    pub deezer_id: String,
+    pub name: String,
+    pub track_count: usize,
+    pub own_track_count: usize, // tracks that are in none of the children
+    pub inherited_track_count: usize,
+    pub total_duration: u64, // in seconds
+    pub direct_children_count: usize,
+    pub transitive_children_count: usize,
+    pub direct_parents_count: usize,
+    pub transitive_parents_count: usize,
+    pub depth: Option<usize>, // distance from the nearest collection without parents, not set if only reachable from a cycle
+    pub in_cycle: bool,
+    pub children: Vec<ChildContribution>, // from the biggest share to the smallest
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct GlobalStatistics {
+    pub collection_count: usize,
+    pub smart_collection_count: usize,
+    pub dependency_count: usize,
+    pub root_collection_count: usize, // collections without parents
+    pub leaf_collection_count: usize, // collections without children
+    pub collections_in_cycle_count: usize,
+    pub max_depth: usize,
+    pub track_count: u64, // sum of the track counts saved at the last refresh of each collection
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum GraphFormat {
+    #[default]
+    Dot,
+    Mermaid,
+    Graphml,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TracklistFormat {
+    #[default]
+    M3u,
+    Xspf,
+    Jspf,
+    Csv,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TracklistView {
+    #[default]
+    Flattened, // the tracks of the collection, with the tracks of its children that a refresh would add
+    Own, // the tracks of the collection that are not in its children
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PlaylistFileFormat {
+    M3u,
+    Csv,
+    Xspf,
+}
+
+// a playlist read from a file exported by another player
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct PlaylistFile {
+    pub title: Option<String>,
+    pub entries: Vec<PlaylistFileEntry>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq)]
+pub struct PlaylistFileEntry {
+    pub position: usize, // from 1, in the order of the file
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub isrc: Option<String>,
+    pub location: Option<String>, // link or path of the track in the file
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TrackMatchMethod {
+    Link, // the entry already was a deezer track link
+    Isrc,
+    Search, // by artist and title
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct MatchedEntry {
+    pub position: usize,
+    pub track_id: String,
+    pub matched_by: TrackMatchMethod,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct FileImportReport {
+    pub deezer_id: String,
+    pub name: String,
+    pub matched: Vec<MatchedEntry>,
+    pub unmatched: Vec<PlaylistFileEntry>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum HierarchyFormat {
+    #[default]
+    Yaml,
+    Toml,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq, Hash)]
+pub struct HierarchyDependency {
+    pub parent_id: String,
+    pub child_id: String,
+}
+
+// the changes making the database match a hierarchy file
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct HierarchyPlan {
+    pub collections_to_add: Vec<String>,
+    pub collections_to_remove: Vec<String>,
+    pub dependencies_to_add: Vec<HierarchyDependency>,
+    pub dependencies_to_remove: Vec<HierarchyDependency>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DependencyAction {
+    Add,
+    Remove,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct DependencyOperation {
+    pub action: DependencyAction,
+    pub parent_id: String,
+    pub child_id: String,
+    pub filter: Option<TrackFilter>, // only for the additions
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DependencyOperationStatus {
+    Applied,
+    Invalid,
+    NotApplied, // valid, but another operation of the batch is invalid
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct DependencyOperationResult {
+    pub index: usize, // position of the operation in the batch
+    pub status: DependencyOperationStatus,
+    pub error: Option<String>,
+}
+
+// the operations of a batch are either all applied or none of them
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct DependencyBatchReport {
+    pub applied: bool,
+    pub results: Vec<DependencyOperationResult>,
+}
+
+// the move is kept when the refresh of the new parent fails
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct MoveCollectionReport {
+    pub moved: bool,
+    pub refresh_error: Option<String>,
+}
+
+// a refresh of all the collections started by the scheduler
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct RefreshRun {
+    pub id: u64,
+    pub started_at: u64,          // timestamp
+    pub finished_at: Option<u64>, // timestamp, not set while the run is in progress
+    pub status: RefreshRunStatus,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RefreshRunStatus {
+    Running,
+    Success,
+    Failed,
+    Skipped, // the previous run was still in progress
 }
@@ -0,0 +1,229 @@
+use std::{collections::HashMap, sync::OnceLock};
+
+use futures::{stream, StreamExt};
+use log::warn;
+use regex::Regex;
+
+use super::{
+    collection_commons::{
+        convert_string_to_u64, get_max_concurrent_requests, get_playlist, log_database_error,
+        log_parameters_error,
+    },
+    controllers::check_id_valid,
+    domain_models::{DuplicateGroup, DuplicateMatch, Track},
+    errors::DomainError,
+};
+use crate::infrastructure::{
+    database::{add_track_isrc, get_track_isrcs},
+    deezer::get_track_isrc,
+};
+
+// version markers that do not change the recording, e.g. "(Remastered 2011)" or "[feat. someone]"
+const VERSION_MARKERS: &str = r"remaster|feat\.?|ft\.|featuring|single version|album version|radio edit|explicit|clean|mono|stereo|deluxe";
+
+static BRACKETED_VERSION_REGEX: OnceLock<Regex> = OnceLock::new();
+static SUFFIX_VERSION_REGEX: OnceLock<Regex> = OnceLock::new();
+static FEATURING_REGEX: OnceLock<Regex> = OnceLock::new();
+static NON_ALPHANUMERIC_REGEX: OnceLock<Regex> = OnceLock::new();
+
+// groups the tracks of the collection that are the same recording under different deezer ids
+pub async fn get_duplicate_groups(id: &str) -> Result<Vec<DuplicateGroup>, DomainError> {
+    log_parameters_error(check_id_valid(id.to_string()))?;
+    let playlist = get_playlist(&convert_string_to_u64(id)).await?;
+    let isrcs = get_tracks_isrcs(&get_tracks_needing_isrc(&playlist.tracks, &[])).await?;
+    let mut duplicate_groups: Vec<DuplicateGroup> = Vec::new();
+    for track in playlist.tracks.into_iter() {
+        let group_index = duplicate_groups
+            .iter()
+            .position(|group| find_duplicate(&track, &isrcs, &group.tracks).is_some());
+        match group_index {
+            Some(group_index) => duplicate_groups[group_index].tracks.push(track),
+            None => duplicate_groups.push(DuplicateGroup {
+                tracks: vec![track],
+            }),
+        }
+    }
+    duplicate_groups.retain(|group| group.tracks.len() > 1);
+    return Ok(duplicate_groups);
+}
+
+// returns the track of the list that is the same recording as the given track, and how it matched
+pub fn find_duplicate<'a>(
+    track: &Track,
+    isrcs: &HashMap<String, String>,
+    tracks: &'a [Track],
+) -> Option<(&'a Track, DuplicateMatch)> {
+    let isrc = isrcs.get(&track.deezer_id).filter(|isrc| !isrc.is_empty());
+    let title = normalize_title(&track.title);
+    let artist = normalize_artist(&track.artist);
+    for other_track in tracks
+        .iter()
+        .filter(|other_track| other_track.deezer_id != track.deezer_id)
+    {
+        if isrc.is_some() && isrcs.get(&other_track.deezer_id) == isrc {
+            return Some((other_track, DuplicateMatch::Isrc));
+        }
+        if title == normalize_title(&other_track.title)
+            && artist == normalize_artist(&other_track.artist)
+        {
+            return Some((other_track, DuplicateMatch::TitleAndArtist));
+        }
+    }
+    return None;
+}
+
+// a candidate track with the same title and artist as another track is already a duplicate, any other
+// one can still be the same recording under another artist credit (feat., other artist id...).
+// Returns the candidate tracks in this case, with all the tracks they are compared with.
+// The isrcs are cached and fetched through the deezer rate limiter, which caps the requests
+pub fn get_tracks_needing_isrc(candidate_tracks: &[Track], known_tracks: &[Track]) -> Vec<Track> {
+    let tracks = [known_tracks, candidate_tracks].concat();
+    let normalized_tracks = tracks
+        .iter()
+        .map(|track| {
+            (
+                track.deezer_id.as_str(),
+                normalize_title(&track.title),
+                normalize_artist(&track.artist),
+            )
+        })
+        .collect::<Vec<_>>();
+    let has_unmatched_candidate =
+        normalized_tracks[known_tracks.len()..]
+            .iter()
+            .any(|(deezer_id, title, artist)| {
+                !normalized_tracks
+                    .iter()
+                    .any(|(other_id, other_title, other_artist)| {
+                        other_id != deezer_id && other_title == title && other_artist == artist
+                    })
+            });
+    if !has_unmatched_candidate {
+        return Vec::new();
+    }
+    return tracks;
+}
+
+// returns the isrcs of the tracks by deezer id, fetching the ones not known yet concurrently
+pub async fn get_tracks_isrcs(tracks: &[Track]) -> Result<HashMap<String, String>, DomainError> {
+    let mut tracks_ids = tracks
+        .iter()
+        .map(|track| track.deezer_id.clone())
+        .collect::<Vec<_>>();
+    tracks_ids.sort();
+    tracks_ids.dedup();
+    let mut isrcs: HashMap<String, String> = match get_track_isrcs(&tracks_ids) {
+        Ok(track_isrcs) => track_isrcs
+            .into_iter()
+            .map(|track_isrc| (track_isrc.deezer_id, track_isrc.isrc))
+            .collect::<HashMap<_, _>>(),
+        Err(e) => {
+            return Err(log_database_error(&format!(
+                "Error while getting the isrcs of the tracks : {:?}",
+                e
+            )));
+        }
+    };
+    let missing_tracks_ids = tracks_ids
+        .into_iter()
+        .filter(|track_id| !isrcs.contains_key(track_id))
+        .collect::<Vec<_>>();
+    let fetched_isrcs = stream::iter(missing_tracks_ids)
+        .map(|track_id| async move {
+            let isrc = get_track_isrc(&track_id).await;
+            (track_id, isrc)
+        })
+        .buffered(get_max_concurrent_requests())
+        .collect::<Vec<_>>()
+        .await;
+    for (track_id, isrc) in fetched_isrcs.into_iter() {
+        match isrc {
+            // an empty isrc is saved as well, so that the tracks without isrc are not looked up again
+            Ok(isrc) => {
+                if let Err(e) = add_track_isrc(&track_id, &isrc) {
+                    return Err(log_database_error(&format!(
+                        "Error while saving the isrc of the track {} : {:?}",
+                        track_id, e
+                    )));
+                }
+                isrcs.insert(track_id, isrc);
+            }
+            // a track without isrc can still be matched by its title and artist
+            Err(e) => warn!("Could not get the isrc of the track {} : {:?}", track_id, e),
+        }
+    }
+    return Ok(isrcs);
+}
+
+pub fn normalize_title(title: &str) -> String {
+    let bracketed_version_regex = BRACKETED_VERSION_REGEX.get_or_init(|| {
+        Regex::new(&format!(r"[\(\[][^\)\]]*({VERSION_MARKERS})[^\)\]]*[\)\]]")).unwrap()
+    });
+    let suffix_version_regex = SUFFIX_VERSION_REGEX
+        .get_or_init(|| Regex::new(&format!(r"\s+-\s+.*({VERSION_MARKERS}).*$")).unwrap());
+    let featuring_regex =
+        FEATURING_REGEX.get_or_init(|| Regex::new(r"\s(feat\.?|ft\.|featuring)\s.*$").unwrap());
+    let title = title.to_lowercase();
+    let title = bracketed_version_regex.replace_all(&title, " ");
+    let title = suffix_version_regex.replace_all(&title, "");
+    let title = featuring_regex.replace_all(&title, "");
+    return remove_punctuation(&title);
+}
+
+pub fn normalize_artist(artist: &str) -> String {
+    return remove_punctuation(&artist.to_lowercase());
+}
+
+fn remove_punctuation(value: &str) -> String {
+    let non_alphanumeric_regex =
+        NON_ALPHANUMERIC_REGEX.get_or_init(|| Regex::new(r"[^\p{L}\p{N}]+").unwrap());
+    return non_alphanumeric_regex
+        .replace_all(value, " ")
+        .trim()
+        .to_string();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track(deezer_id: &str, title: &str, artist: &str) -> Track {
+        return Track {
+            id: 0,
+            deezer_id: deezer_id.to_string(),
+            title: title.to_string(),
+            link: String::new(),
+            artist: artist.to_string(),
+            artist_id: 0,
+            duration: 0,
+            explicit: false,
+            time_add: 0,
+        };
+    }
+
+    #[test]
+    fn the_same_recording_credited_to_different_artists_is_compared_by_isrc() {
+        let known_tracks = vec![track("1", "Song", "Artist A")];
+        let candidate_tracks = vec![track("2", "Song (feat. B)", "Artist A & Artist B")];
+        let tracks_needing_isrc = get_tracks_needing_isrc(&candidate_tracks, &known_tracks);
+        let mut ids = tracks_needing_isrc
+            .iter()
+            .map(|track| track.deezer_id.as_str())
+            .collect::<Vec<_>>();
+        ids.sort();
+        assert_eq!(ids, vec!["1", "2"]);
+        let isrcs = HashMap::from([
+            ("1".to_string(), "TEST00000001".to_string()),
+            ("2".to_string(), "TEST00000001".to_string()),
+        ]);
+        let duplicate = find_duplicate(&candidate_tracks[0], &isrcs, &known_tracks);
+        assert!(matches!(duplicate, Some((_, DuplicateMatch::Isrc))));
+    }
+
+    #[test]
+    fn a_same_title_and_artist_does_not_need_isrc() {
+        let known_tracks = vec![track("1", "Song", "Artist A")];
+        let candidate_tracks = vec![track("2", "Song (Remastered 2011)", "artist a")];
+        assert!(get_tracks_needing_isrc(&candidate_tracks, &known_tracks).is_empty());
+    }
+}
@@ -1,6 +1,21 @@
+use std::fmt;
+
+// each error carries the message it was logged with, e.g. to save why a refresh failed
 #[derive(Debug)]
 pub enum DomainError {
-    DomainParamError(),
-    DomainDataError(),
-    DomainMusicServiceError(),
+    DomainParamError(String),
+    DomainDataError(String),
+    DomainMusicServiceError(String),
+    DomainForbiddenError(String),
+}
+
+impl fmt::Display for DomainError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DomainError::DomainParamError(message)
+            | DomainError::DomainDataError(message)
+            | DomainError::DomainMusicServiceError(message)
+            | DomainError::DomainForbiddenError(message) => write!(f, "{}", message),
+        }
+    }
 }
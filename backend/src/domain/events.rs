@@ -0,0 +1,113 @@
+use std::{
+    collections::VecDeque,
+    sync::{Mutex, OnceLock},
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::{self, Receiver, Sender};
+
+// Number of events kept for slow subscribers before they start lagging
+const EVENTS_CHANNEL_CAPACITY: usize = 256;
+// Number of past events that can be replayed to a subscriber
+const EVENTS_HISTORY_CAPACITY: usize = 256;
+
+static EVENTS_SENDER: OnceLock<Sender<PublishedEvent>> = OnceLock::new();
+// the last published events, the oldest first
+static EVENTS_HISTORY: Mutex<VecDeque<PublishedEvent>> = Mutex::new(VecDeque::new());
+
+// the ids start at 1 and increase with each event, so that a subscriber can resume after the last one it saw
+#[derive(Debug, Clone)]
+pub struct PublishedEvent {
+    pub id: u64,
+    pub event: CollectionEvent,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CollectionEvent {
+    CollectionCreated {
+        deezer_id: String,
+        name: String,
+    },
+    CollectionRemoved {
+        deezer_id: String,
+    },
+    CollectionRenamed {
+        deezer_id: String,
+        name: String,
+    },
+    DependencyAdded {
+        parent_id: String,
+        child_id: String,
+    },
+    DependencyRemoved {
+        parent_id: String,
+        child_id: String,
+    },
+    RefreshStarted {
+        deezer_id: String,
+    },
+    CollectionRefreshed {
+        deezer_id: String,
+        tracks_added: Vec<String>,
+    },
+    RefreshFailed {
+        deezer_id: String,
+        error: String,
+    },
+    // the tracks were added by the refresh, but could not be ordered
+    OrderingFailed {
+        deezer_id: String,
+        error: String,
+    },
+}
+
+impl CollectionEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CollectionEvent::CollectionCreated { .. } => "collection_created",
+            CollectionEvent::CollectionRemoved { .. } => "collection_removed",
+            CollectionEvent::CollectionRenamed { .. } => "collection_renamed",
+            CollectionEvent::DependencyAdded { .. } => "dependency_added",
+            CollectionEvent::DependencyRemoved { .. } => "dependency_removed",
+            CollectionEvent::RefreshStarted { .. } => "refresh_started",
+            CollectionEvent::CollectionRefreshed { .. } => "collection_refreshed",
+            CollectionEvent::RefreshFailed { .. } => "refresh_failed",
+            CollectionEvent::OrderingFailed { .. } => "ordering_failed",
+        }
+    }
+}
+
+fn get_events_sender() -> &'static Sender<PublishedEvent> {
+    return EVENTS_SENDER.get_or_init(|| broadcast::channel(EVENTS_CHANNEL_CAPACITY).0);
+}
+
+// publishes an event to every subscriber. Events are dropped if nobody is listening,
+// but are kept in the history
+pub fn publish_event(event: CollectionEvent) {
+    let mut history = EVENTS_HISTORY.lock().unwrap();
+    let published_event = PublishedEvent {
+        id: history.back().map_or(1, |last_event| last_event.id + 1),
+        event,
+    };
+    if history.len() == EVENTS_HISTORY_CAPACITY {
+        history.pop_front();
+    }
+    history.push_back(published_event.clone());
+    let _ = get_events_sender().send(published_event);
+}
+
+// returns the events of the history published after the since id, and the receiver of the next ones.
+// Both are read under the history lock, so that no event is missed or received twice
+pub fn subscribe_events(since: Option<u64>) -> (Vec<PublishedEvent>, Receiver<PublishedEvent>) {
+    let history = EVENTS_HISTORY.lock().unwrap();
+    let past_events = match since {
+        Some(since) => history
+            .iter()
+            .filter(|published_event| published_event.id > since)
+            .cloned()
+            .collect::<Vec<_>>(),
+        None => vec![],
+    };
+    return (past_events, get_events_sender().subscribe());
+}
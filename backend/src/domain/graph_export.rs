@@ -0,0 +1,191 @@
+use super::{
+    collection_commons::escape_xml,
+    collection_graph::{get_children, get_collection_graph, is_cycle_dependency, CollectionGraph},
+    collection_management::list_collections,
+    domain_models::{CollectionListElement, GraphFormat},
+    errors::DomainError,
+};
+
+const CYCLE_COLOR: &str = "red";
+
+// a dependency of the graph, from the parent to the child
+struct GraphEdge<'a> {
+    parent_id: &'a str,
+    child_id: &'a str,
+    in_cycle: bool,
+}
+
+// renders the collections and their dependencies. The dependencies that are part of a cycle are highlighted
+pub fn export_collection_graph(format: &GraphFormat) -> Result<String, DomainError> {
+    let collections = list_collections()?;
+    let graph = get_collection_graph()?;
+    let edges = get_graph_edges(&graph);
+    match format {
+        GraphFormat::Dot => return Ok(render_dot(&collections, &edges)),
+        GraphFormat::Mermaid => return Ok(render_mermaid(&collections, &edges)),
+        GraphFormat::Graphml => return Ok(render_graphml(&collections, &edges)),
+    }
+}
+
+fn get_graph_edges(graph: &CollectionGraph) -> Vec<GraphEdge<'_>> {
+    let mut edges: Vec<GraphEdge> = Vec::new();
+    for parent_id in graph.collections.iter() {
+        for child_id in get_children(graph, parent_id).iter() {
+            edges.push(GraphEdge {
+                parent_id,
+                child_id,
+                in_cycle: is_cycle_dependency(graph, parent_id, child_id),
+            });
+        }
+    }
+    return edges;
+}
+
+fn get_node_id(deezer_id: &str) -> String {
+    return format!("c{}", deezer_id);
+}
+
+fn get_track_count_label(collection: &CollectionListElement) -> String {
+    match collection.metadata.track_count {
+        Some(1) => return "1 track".to_string(),
+        Some(track_count) => return format!("{} tracks", track_count),
+        None => return "unknown track count".to_string(),
+    }
+}
+
+fn render_dot(collections: &[CollectionListElement], edges: &[GraphEdge]) -> String {
+    let escape = |value: &str| value.replace('\\', "\\\\").replace('"', "\\\"");
+    let mut dot = String::from("digraph collections {\n    node [shape=box];\n");
+    for collection in collections.iter() {
+        dot.push_str(&format!(
+            "    {} [label=\"{}\\n{}\"];\n",
+            get_node_id(&collection.deezer_id),
+            escape(&collection.name),
+            get_track_count_label(collection)
+        ));
+    }
+    for edge in edges.iter() {
+        let attributes = if edge.in_cycle {
+            format!(" [color={}, penwidth=2]", CYCLE_COLOR)
+        } else {
+            "".to_string()
+        };
+        dot.push_str(&format!(
+            "    {} -> {}{};\n",
+            get_node_id(edge.parent_id),
+            get_node_id(edge.child_id),
+            attributes
+        ));
+    }
+    dot.push_str("}\n");
+    return dot;
+}
+
+fn render_mermaid(collections: &[CollectionListElement], edges: &[GraphEdge]) -> String {
+    // the characters closing the label or breaking the line are replaced by their mermaid entity codes
+    let escape = |value: &str| {
+        value
+            .replace('#', "#35;")
+            .replace('"', "#quot;")
+            .replace('[', "#91;")
+            .replace(']', "#93;")
+            .replace('|', "#124;")
+            .replace('<', "#60;")
+            .replace('>', "#62;")
+            .replace(['\r', '\n'], " ")
+    };
+    let mut mermaid = String::from("graph TD\n");
+    for collection in collections.iter() {
+        mermaid.push_str(&format!(
+            "    {}[\"{}<br/>{}\"]\n",
+            get_node_id(&collection.deezer_id),
+            escape(&collection.name),
+            get_track_count_label(collection)
+        ));
+    }
+    for edge in edges.iter() {
+        mermaid.push_str(&format!(
+            "    {} --> {}\n",
+            get_node_id(edge.parent_id),
+            get_node_id(edge.child_id)
+        ));
+    }
+    // the links are styled by their index, in order of declaration
+    for (index, _) in edges.iter().enumerate().filter(|(_, edge)| edge.in_cycle) {
+        mermaid.push_str(&format!(
+            "    linkStyle {} stroke:{},stroke-width:2px\n",
+            index, CYCLE_COLOR
+        ));
+    }
+    return mermaid;
+}
+
+fn render_graphml(collections: &[CollectionListElement], edges: &[GraphEdge]) -> String {
+    let mut graphml = String::from(concat!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+        "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+        "  <key id=\"name\" for=\"node\" attr.name=\"name\" attr.type=\"string\"/>\n",
+        "  <key id=\"track_count\" for=\"node\" attr.name=\"track_count\" attr.type=\"long\"/>\n",
+        "  <key id=\"in_cycle\" for=\"edge\" attr.name=\"in_cycle\" attr.type=\"boolean\">\n",
+        "    <default>false</default>\n",
+        "  </key>\n",
+        "  <graph id=\"collections\" edgedefault=\"directed\">\n"
+    ));
+    for collection in collections.iter() {
+        graphml.push_str(&format!(
+            "    <node id=\"{}\">\n      <data key=\"name\">{}</data>\n",
+            get_node_id(&collection.deezer_id),
+            escape_xml(&collection.name)
+        ));
+        if let Some(track_count) = collection.metadata.track_count {
+            graphml.push_str(&format!(
+                "      <data key=\"track_count\">{}</data>\n",
+                track_count
+            ));
+        }
+        graphml.push_str("    </node>\n");
+    }
+    for edge in edges.iter() {
+        graphml.push_str(&format!(
+            "    <edge source=\"{}\" target=\"{}\">\n      <data key=\"in_cycle\">{}</data>\n    </edge>\n",
+            get_node_id(edge.parent_id),
+            get_node_id(edge.child_id),
+            edge.in_cycle
+        ));
+    }
+    graphml.push_str("  </graph>\n</graphml>\n");
+    return graphml;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::domain_models::{CollectionKind, CollectionMetadata};
+
+    #[test]
+    fn mermaid_labels_escape_the_closing_characters() {
+        let collections = vec![CollectionListElement {
+            name: "Rock [80's] | \"best\" <live>\n#1".to_string(),
+            deezer_id: "123456".to_string(),
+            url: "https://www.deezer.com/playlist/123456".to_string(),
+            kind: CollectionKind::Standard,
+            metadata: CollectionMetadata {
+                description: None,
+                tags: Vec::new(),
+                created_at: None,
+                last_refreshed_at: None,
+                last_refresh_status: None,
+                last_refresh_error: None,
+                last_refresh_tracks_added: None,
+                track_count: Some(1),
+            },
+        }];
+        assert_eq!(
+            render_mermaid(&collections, &[]),
+            concat!(
+                "graph TD\n",
+                "    c123456[\"Rock #91;80's#93; #124; #quot;best#quot; #60;live#62; #35;1<br/>1 track\"]\n"
+            )
+        );
+    }
+}
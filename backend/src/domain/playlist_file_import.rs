@@ -0,0 +1,321 @@
+use std::collections::HashSet;
+
+use log::error;
+
+use super::{
+    collection_commons::{create_new_playlist, log_deezer_error, log_parameters_error},
+    collection_health::DEEZER_ERROR_CODE_NO_DATA,
+    controllers::check_collection_name_valid,
+    deezer_links::parse_track_link,
+    domain_models::{
+        FileImportReport, MatchedEntry, PlaylistFileEntry, PlaylistFileFormat, TrackMatchMethod,
+    },
+    errors::DomainError,
+    playlist_files::{detect_playlist_file_format, parse_playlist_file},
+};
+use crate::infrastructure::{
+    deezer::{add_tracks_to_playlist, get_track_by_isrc, search_tracks},
+    deezer_models::TrackIsrcResponse,
+};
+
+// the lookups used to match the entries of a file with deezer tracks, so that the matching can run against a stub
+pub trait TrackMatcher {
+    // returns the id of the track with this isrc, if any
+    async fn find_track_by_isrc(&self, isrc: &str) -> Result<Option<String>, DomainError>;
+    // returns the id of the best match for the title, and the artist if known
+    async fn search_track(
+        &self,
+        artist: Option<&str>,
+        title: &str,
+    ) -> Result<Option<String>, DomainError>;
+}
+
+pub struct DeezerTrackMatcher;
+
+impl TrackMatcher for DeezerTrackMatcher {
+    async fn find_track_by_isrc(&self, isrc: &str) -> Result<Option<String>, DomainError> {
+        match get_track_by_isrc(isrc).await {
+            Ok(TrackIsrcResponse::Track(track)) => return Ok(Some(track.id.to_string())),
+            Ok(TrackIsrcResponse::Error(response)) => {
+                if response.error.code == DEEZER_ERROR_CODE_NO_DATA {
+                    return Ok(None);
+                }
+                return Err(log_deezer_error(&format!(
+                    "Error getting the track with isrc {} : {:?}",
+                    isrc, response.error
+                )));
+            }
+            Err(e) => {
+                return Err(log_deezer_error(&format!(
+                    "Error getting the track with isrc {} : {:?}",
+                    isrc, e
+                )));
+            }
+        }
+    }
+
+    async fn search_track(
+        &self,
+        artist: Option<&str>,
+        title: &str,
+    ) -> Result<Option<String>, DomainError> {
+        // the quotes would end the values of the advanced search
+        let query = match artist {
+            Some(artist) => format!(
+                "artist:\"{}\" track:\"{}\"",
+                artist.replace('"', " "),
+                title.replace('"', " ")
+            ),
+            None => format!("track:\"{}\"", title.replace('"', " ")),
+        };
+        match search_tracks(&query).await {
+            Ok(page) => {
+                return Ok(page
+                    .data
+                    .into_iter()
+                    .next()
+                    .map(|track| track.id.to_string()))
+            }
+            Err(e) => {
+                return Err(log_deezer_error(&format!(
+                    "Error searching the track {} : {:?}",
+                    query, e
+                )));
+            }
+        }
+    }
+}
+
+// matches each entry with a deezer track, by its deezer link, then its isrc, then its artist and title
+pub async fn match_playlist_entries(
+    matcher: &impl TrackMatcher,
+    entries: Vec<PlaylistFileEntry>,
+) -> Result<(Vec<MatchedEntry>, Vec<PlaylistFileEntry>), DomainError> {
+    let mut matched: Vec<MatchedEntry> = Vec::new();
+    let mut unmatched: Vec<PlaylistFileEntry> = Vec::new();
+    for entry in entries.into_iter() {
+        match match_playlist_entry(matcher, &entry).await? {
+            Some((track_id, matched_by)) => matched.push(MatchedEntry {
+                position: entry.position,
+                track_id,
+                matched_by,
+            }),
+            None => unmatched.push(entry),
+        }
+    }
+    return Ok((matched, unmatched));
+}
+
+async fn match_playlist_entry(
+    matcher: &impl TrackMatcher,
+    entry: &PlaylistFileEntry,
+) -> Result<Option<(String, TrackMatchMethod)>, DomainError> {
+    if let Some(track_id) = entry.location.as_deref().and_then(parse_track_link) {
+        return Ok(Some((track_id.to_string(), TrackMatchMethod::Link)));
+    }
+    if let Some(isrc) = &entry.isrc {
+        if let Some(track_id) = matcher.find_track_by_isrc(isrc).await? {
+            return Ok(Some((track_id, TrackMatchMethod::Isrc)));
+        }
+    }
+    if let Some(title) = &entry.title {
+        if let Some(track_id) = matcher.search_track(entry.artist.as_deref(), title).await? {
+            return Ok(Some((track_id, TrackMatchMethod::Search)));
+        }
+    }
+    return Ok(None);
+}
+
+// creates a collection with the tracks of a file exported by another player
+// the name defaults to the title of the playlist in the file, and the format is guessed if not given
+pub async fn import_playlist_file(
+    name: Option<String>,
+    format: Option<PlaylistFileFormat>,
+    content: &str,
+) -> Result<FileImportReport, DomainError> {
+    let format = format.unwrap_or_else(|| detect_playlist_file_format(content));
+    let playlist_file = match parse_playlist_file(&format, content) {
+        Ok(playlist_file) => playlist_file,
+        Err(failures) => {
+            error!("Handler - error in input parameters : {}", failures);
+            return Err(DomainError::DomainParamError(failures));
+        }
+    };
+    let name = name
+        .filter(|name| !name.trim().is_empty())
+        .or(playlist_file.title)
+        .unwrap_or_default();
+    let mut failures = check_collection_name_valid(&name);
+    if playlist_file.entries.is_empty() {
+        failures.push_str("- the file should contain at least one track\n");
+    }
+    log_parameters_error(failures)?;
+    // the tracks are matched before creating the playlist, so that a failing search does not leave an empty collection
+    let (matched, unmatched) =
+        match_playlist_entries(&DeezerTrackMatcher, playlist_file.entries).await?;
+    let id = create_new_playlist(&name).await?.to_string();
+    let mut added_track_ids: HashSet<&str> = HashSet::new();
+    let track_ids = matched
+        .iter()
+        .filter(|entry| added_track_ids.insert(&entry.track_id))
+        .map(|entry| entry.track_id.clone())
+        .collect::<Vec<_>>();
+    if let Err(e) = add_tracks_to_playlist(&id, track_ids).await {
+        return Err(log_deezer_error(&format!(
+            "Error adding the imported tracks to playlist {} : {:?}",
+            id, e
+        )));
+    }
+    return Ok(FileImportReport {
+        deezer_id: id,
+        name,
+        matched,
+        unmatched,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::*;
+
+    // answers from fixed isrcs and titles, and records the lookups it was asked for
+    struct StubTrackMatcher {
+        isrcs: Vec<(&'static str, &'static str)>,
+        titles: Vec<(&'static str, &'static str)>,
+        lookups: RefCell<Vec<String>>,
+    }
+
+    impl StubTrackMatcher {
+        fn new(
+            isrcs: Vec<(&'static str, &'static str)>,
+            titles: Vec<(&'static str, &'static str)>,
+        ) -> StubTrackMatcher {
+            return StubTrackMatcher {
+                isrcs,
+                titles,
+                lookups: RefCell::new(Vec::new()),
+            };
+        }
+    }
+
+    impl TrackMatcher for StubTrackMatcher {
+        async fn find_track_by_isrc(&self, isrc: &str) -> Result<Option<String>, DomainError> {
+            self.lookups.borrow_mut().push(format!("isrc:{}", isrc));
+            return Ok(self
+                .isrcs
+                .iter()
+                .find(|(known_isrc, _)| *known_isrc == isrc)
+                .map(|(_, track_id)| track_id.to_string()));
+        }
+
+        async fn search_track(
+            &self,
+            artist: Option<&str>,
+            title: &str,
+        ) -> Result<Option<String>, DomainError> {
+            self.lookups.borrow_mut().push(format!(
+                "search:{}/{}",
+                artist.unwrap_or_default(),
+                title
+            ));
+            return Ok(self
+                .titles
+                .iter()
+                .find(|(known_title, _)| *known_title == title)
+                .map(|(_, track_id)| track_id.to_string()));
+        }
+    }
+
+    fn entry(
+        position: usize,
+        title: Option<&str>,
+        isrc: Option<&str>,
+        location: Option<&str>,
+    ) -> PlaylistFileEntry {
+        return PlaylistFileEntry {
+            position,
+            title: title.map(String::from),
+            artist: Some("artist".to_string()),
+            isrc: isrc.map(String::from),
+            location: location.map(String::from),
+        };
+    }
+
+    fn matched(position: usize, track_id: &str, matched_by: TrackMatchMethod) -> MatchedEntry {
+        return MatchedEntry {
+            position,
+            track_id: track_id.to_string(),
+            matched_by,
+        };
+    }
+
+    #[tokio::test]
+    async fn matches_a_deezer_link_without_any_lookup() {
+        let matcher = StubTrackMatcher::new(vec![("ISRC1", "2")], vec![("title", "3")]);
+        let entries = vec![entry(
+            1,
+            Some("title"),
+            Some("ISRC1"),
+            Some("https://www.deezer.com/track/1"),
+        )];
+        let (matched_entries, unmatched) = match_playlist_entries(&matcher, entries).await.unwrap();
+        assert_eq!(
+            matched_entries,
+            vec![matched(1, "1", TrackMatchMethod::Link)]
+        );
+        assert!(unmatched.is_empty());
+        assert!(matcher.lookups.borrow().is_empty());
+    }
+
+    #[tokio::test]
+    async fn matches_by_isrc_before_searching() {
+        let matcher = StubTrackMatcher::new(vec![("ISRC1", "2")], vec![("title", "3")]);
+        let entries = vec![entry(
+            1,
+            Some("title"),
+            Some("ISRC1"),
+            Some("/music/title.mp3"),
+        )];
+        let (matched_entries, _) = match_playlist_entries(&matcher, entries).await.unwrap();
+        assert_eq!(
+            matched_entries,
+            vec![matched(1, "2", TrackMatchMethod::Isrc)]
+        );
+        assert_eq!(*matcher.lookups.borrow(), vec!["isrc:ISRC1"]);
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_the_search_when_the_isrc_is_unknown() {
+        let matcher = StubTrackMatcher::new(vec![], vec![("title", "3")]);
+        let entries = vec![entry(1, Some("title"), Some("ISRC1"), None)];
+        let (matched_entries, _) = match_playlist_entries(&matcher, entries).await.unwrap();
+        assert_eq!(
+            matched_entries,
+            vec![matched(1, "3", TrackMatchMethod::Search)]
+        );
+        assert_eq!(
+            *matcher.lookups.borrow(),
+            vec!["isrc:ISRC1", "search:artist/title"]
+        );
+    }
+
+    #[tokio::test]
+    async fn keeps_the_entries_without_any_match() {
+        let matcher = StubTrackMatcher::new(vec![], vec![("title", "3")]);
+        let entries = vec![
+            entry(1, Some("unknown title"), Some("ISRC1"), None),
+            entry(2, None, None, Some("/music/no tags.mp3")),
+            entry(3, Some("title"), None, None),
+        ];
+        let (matched_entries, unmatched) = match_playlist_entries(&matcher, entries.clone())
+            .await
+            .unwrap();
+        assert_eq!(
+            matched_entries,
+            vec![matched(3, "3", TrackMatchMethod::Search)]
+        );
+        assert_eq!(unmatched, entries[..2].to_vec());
+    }
+}
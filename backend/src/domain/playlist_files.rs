@@ -0,0 +1,235 @@
+use std::{collections::HashMap, path::Path};
+
+use quick_xml::{events::Event, Reader};
+
+use super::domain_models::{PlaylistFile, PlaylistFileEntry, PlaylistFileFormat};
+
+const M3U_HEADER: &str = "#EXTM3U";
+const M3U_PLAYLIST_DIRECTIVE: &str = "#PLAYLIST:";
+const M3U_TRACK_INFO_DIRECTIVE: &str = "#EXTINF:";
+const ARTIST_TITLE_SEPARATOR: &str = " - ";
+// the names given to the columns by the usual exports, in lower case
+const CSV_TITLE_COLUMNS: [&str; 5] = ["title", "track", "track name", "name", "song"];
+const CSV_ARTIST_COLUMNS: [&str; 5] = [
+    "artist",
+    "artist name",
+    "artist name(s)",
+    "artists",
+    "creator",
+];
+const CSV_ISRC_COLUMNS: [&str; 1] = ["isrc"];
+const CSV_LOCATION_COLUMNS: [&str; 4] = ["link", "url", "location", "uri"];
+
+// guesses the format from the start of the file, csv being the fallback
+pub fn detect_playlist_file_format(content: &str) -> PlaylistFileFormat {
+    let content = content.trim_start_matches('\u{feff}').trim_start();
+    if content.starts_with('<') {
+        return PlaylistFileFormat::Xspf;
+    }
+    if content.starts_with(M3U_HEADER) || content.starts_with(M3U_TRACK_INFO_DIRECTIVE) {
+        return PlaylistFileFormat::M3u;
+    }
+    return PlaylistFileFormat::Csv;
+}
+
+// returns the playlist, or the reason why the file can not be read
+pub fn parse_playlist_file(
+    format: &PlaylistFileFormat,
+    content: &str,
+) -> Result<PlaylistFile, String> {
+    let content = content.trim_start_matches('\u{feff}');
+    match format {
+        PlaylistFileFormat::M3u => return Ok(parse_m3u(content)),
+        PlaylistFileFormat::Csv => return parse_csv(content),
+        PlaylistFileFormat::Xspf => return parse_xspf(content),
+    }
+}
+
+// keeps the isrc only if it is well formed, e.g. "us-rc1-76-07839" becomes "USRC17607839"
+pub fn normalize_isrc(isrc: &str) -> Option<String> {
+    let isrc = isrc
+        .trim()
+        .trim_start_matches("urn:")
+        .trim_start_matches("isrc:")
+        .replace('-', "")
+        .to_uppercase();
+    let is_valid = isrc.len() == 12
+        && isrc.is_ascii()
+        && isrc[..2].chars().all(|c| c.is_ascii_alphabetic())
+        && isrc[2..5].chars().all(|c| c.is_ascii_alphanumeric())
+        && isrc[5..].chars().all(|c| c.is_ascii_digit());
+    if is_valid {
+        return Some(isrc);
+    }
+    return None;
+}
+
+fn get_non_empty(value: &str) -> Option<String> {
+    let value = value.trim();
+    if value.is_empty() {
+        return None;
+    }
+    return Some(value.to_string());
+}
+
+// "Artist - Title" gives both, anything else is only a title
+fn split_artist_and_title(value: &str) -> (Option<String>, Option<String>) {
+    match value.split_once(ARTIST_TITLE_SEPARATOR) {
+        Some((artist, title)) => return (get_non_empty(artist), get_non_empty(title)),
+        None => return (None, get_non_empty(value)),
+    }
+}
+
+fn is_empty_entry(entry: &PlaylistFileEntry) -> bool {
+    return entry.title.is_none()
+        && entry.artist.is_none()
+        && entry.isrc.is_none()
+        && entry.location.is_none();
+}
+
+fn parse_m3u(content: &str) -> PlaylistFile {
+    let mut playlist_file = PlaylistFile::default();
+    let mut track_info: Option<(Option<String>, Option<String>)> = None;
+    for line in content.lines().map(str::trim) {
+        if let Some(title) = line.strip_prefix(M3U_PLAYLIST_DIRECTIVE) {
+            playlist_file.title = get_non_empty(title);
+        } else if let Some(info) = line.strip_prefix(M3U_TRACK_INFO_DIRECTIVE) {
+            // #EXTINF:<duration>,<artist> - <title>
+            let display_name = info.split_once(',').map_or("", |(_, name)| name);
+            track_info = Some(split_artist_and_title(display_name));
+        } else if !line.is_empty() && !line.starts_with('#') {
+            // without #EXTINF, the name of a local file usually is "<artist> - <title>.<extension>"
+            let (artist, title) = match track_info.take() {
+                Some(track_info) => track_info,
+                None if !line.contains("://") => split_artist_and_title(
+                    &Path::new(&line.replace('\\', "/"))
+                        .file_stem()
+                        .map_or("".to_string(), |stem| stem.to_string_lossy().to_string()),
+                ),
+                None => (None, None),
+            };
+            playlist_file.entries.push(PlaylistFileEntry {
+                position: playlist_file.entries.len() + 1,
+                title,
+                artist,
+                isrc: None,
+                location: Some(line.to_string()),
+            });
+        }
+    }
+    return playlist_file;
+}
+
+fn parse_csv(content: &str) -> Result<PlaylistFile, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(content.as_bytes());
+    let columns: HashMap<String, usize> = match reader.headers() {
+        Ok(headers) => headers
+            .iter()
+            .enumerate()
+            .map(|(index, header)| (header.trim().to_lowercase(), index))
+            .collect::<HashMap<_, _>>(),
+        Err(e) => return Err(format!("- the csv header can not be read : {}\n", e)),
+    };
+    let find_column = |names: &[&str]| names.iter().find_map(|name| columns.get(*name).copied());
+    let title_column = find_column(&CSV_TITLE_COLUMNS);
+    let artist_column = find_column(&CSV_ARTIST_COLUMNS);
+    let isrc_column = find_column(&CSV_ISRC_COLUMNS);
+    let location_column = find_column(&CSV_LOCATION_COLUMNS);
+    if title_column.is_none() && isrc_column.is_none() && location_column.is_none() {
+        return Err("- the csv file should have a title, isrc or link column\n".to_string());
+    }
+    let mut playlist_file = PlaylistFile::default();
+    for record in reader.records() {
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => return Err(format!("- the csv file can not be read : {}\n", e)),
+        };
+        let get_field = |column: Option<usize>| {
+            column
+                .and_then(|column| record.get(column))
+                .and_then(get_non_empty)
+        };
+        let entry = PlaylistFileEntry {
+            position: playlist_file.entries.len() + 1,
+            title: get_field(title_column),
+            artist: get_field(artist_column),
+            isrc: get_field(isrc_column).and_then(|isrc| normalize_isrc(&isrc)),
+            location: get_field(location_column),
+        };
+        if !is_empty_entry(&entry) {
+            playlist_file.entries.push(entry);
+        }
+    }
+    return Ok(playlist_file);
+}
+
+// reads the title of the playlist and the location, title, creator and isrc identifier of the tracks
+fn parse_xspf(content: &str) -> Result<PlaylistFile, String> {
+    let mut reader = Reader::from_str(content);
+    let mut playlist_file = PlaylistFile::default();
+    let mut elements: Vec<String> = Vec::new();
+    let mut entry: Option<PlaylistFileEntry> = None;
+    loop {
+        let text = match reader.read_event() {
+            Ok(Event::Start(element)) => {
+                let name = String::from_utf8_lossy(element.local_name().as_ref()).to_string();
+                if name == "track" {
+                    entry = Some(PlaylistFileEntry::default());
+                }
+                elements.push(name);
+                continue;
+            }
+            Ok(Event::End(_)) => {
+                if elements.pop().as_deref() == Some("track") {
+                    if let Some(mut entry) = entry.take() {
+                        if !is_empty_entry(&entry) {
+                            entry.position = playlist_file.entries.len() + 1;
+                            playlist_file.entries.push(entry);
+                        }
+                    }
+                }
+                continue;
+            }
+            Ok(Event::Text(text)) => match text.unescape() {
+                Ok(text) => text.to_string(),
+                Err(e) => {
+                    return Err(format!(
+                        "- the xspf file is invalid at position {} : {}\n",
+                        reader.buffer_position(),
+                        e
+                    ));
+                }
+            },
+            Ok(Event::CData(text)) => String::from_utf8_lossy(&text).to_string(),
+            Ok(Event::Eof) => break,
+            Ok(_) => continue,
+            Err(e) => {
+                return Err(format!(
+                    "- the xspf file is invalid at position {} : {}\n",
+                    reader.buffer_position(),
+                    e
+                ));
+            }
+        };
+        let element = elements.last().map_or("", String::as_str);
+        let parent = elements
+            .len()
+            .checked_sub(2)
+            .map_or("", |index| elements[index].as_str());
+        match (&mut entry, parent, element) {
+            (Some(entry), "track", "title") => entry.title = get_non_empty(&text),
+            (Some(entry), "track", "creator") => entry.artist = get_non_empty(&text),
+            (Some(entry), "track", "location") if entry.location.is_none() => {
+                entry.location = get_non_empty(&text)
+            }
+            (Some(entry), "track", "identifier") if entry.isrc.is_none() => {
+                entry.isrc = normalize_isrc(&text)
+            }
+            (None, "playlist", "title") => playlist_file.title = get_non_empty(&text),
+            _ => {}
+        }
+    }
+    return Ok(playlist_file);
+}
@@ -0,0 +1,300 @@
+use std::{
+    str::FromStr,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+use chrono::Local;
+use cron::Schedule;
+use log::{error, info, warn};
+use tokio::time::sleep;
+
+use super::{
+    collection_commons::{convert_string_to_u64, get_current_timestamp, log_database_error},
+    collection_management::update_all_collections,
+    converter::{convert_refresh_run, convert_refresh_run_status_to_string},
+    domain_models::{RefreshRun, RefreshRunStatus},
+    errors::DomainError,
+};
+use crate::{
+    common::common::get_env_variable,
+    infrastructure::database::{add_refresh_run, list_refresh_runs, update_refresh_run_outcome},
+};
+
+// Number of runs returned, the older ones are kept in the database
+const RECENT_REFRESH_RUNS_LIMIT: i64 = 20;
+
+// set while a scheduled refresh of all the collections is in progress
+static REFRESH_RUNNING: AtomicBool = AtomicBool::new(false);
+
+// clears REFRESH_RUNNING when the run ends, even if it panics, so that the next runs are not skipped forever
+struct RefreshRunningGuard;
+
+impl Drop for RefreshRunningGuard {
+    fn drop(&mut self) {
+        REFRESH_RUNNING.store(false, Ordering::SeqCst);
+    }
+}
+
+enum RefreshSchedule {
+    Cron(Box<Schedule>),
+    Interval(Duration),
+}
+
+// refreshes all the collections on REFRESH_CRON, or every REFRESH_INTERVAL seconds, if one of them is set
+// returns the invalid settings, the server should not start without its schedule
+pub fn start_refresh_scheduler() -> Result<bool, String> {
+    let Some(refresh_schedule) = get_refresh_schedule()? else {
+        info!("Scheduler : disabled, neither REFRESH_CRON nor REFRESH_INTERVAL is set");
+        return Ok(false);
+    };
+    tokio::spawn(async move {
+        loop {
+            match get_delay_before_next_run(&refresh_schedule) {
+                Some(delay) => sleep(delay).await,
+                None => {
+                    warn!("Scheduler : REFRESH_CRON has no upcoming run, stopping the scheduler");
+                    return;
+                }
+            }
+            // the runs are spawned so that a long run does not shift the schedule
+            if REFRESH_RUNNING.swap(true, Ordering::SeqCst) {
+                warn!("Scheduler : the previous refresh is still in progress, skipping this one");
+                let now = get_current_timestamp() as i64;
+                record_refresh_run(&now, Some(&now), &RefreshRunStatus::Skipped);
+                continue;
+            }
+            let running_guard = RefreshRunningGuard;
+            tokio::spawn(async move {
+                let _running_guard = running_guard;
+                run_scheduled_refresh().await;
+            });
+        }
+    });
+    return Ok(true);
+}
+
+// returns the last runs of the scheduler, the most recent first
+pub fn get_refresh_runs() -> Result<Vec<RefreshRun>, DomainError> {
+    match list_refresh_runs(RECENT_REFRESH_RUNS_LIMIT) {
+        Ok(runs) => {
+            return Ok(runs
+                .into_iter()
+                .map(convert_refresh_run)
+                .collect::<Vec<_>>())
+        }
+        Err(e) => {
+            return Err(log_database_error(&format!(
+                "Error while listing the refresh runs : {:?}",
+                e
+            )));
+        }
+    }
+}
+
+async fn run_scheduled_refresh() {
+    info!("Scheduler : refreshing all collections");
+    let started_at = get_current_timestamp() as i64;
+    let id = record_refresh_run(&started_at, None, &RefreshRunStatus::Running);
+    let (status, error) = match update_all_collections().await {
+        Ok(_) => (RefreshRunStatus::Success, None),
+        Err(e) => {
+            error!(
+                "Scheduler : error while refreshing all collections : {:?}",
+                e
+            );
+            (RefreshRunStatus::Failed, Some(e.to_string()))
+        }
+    };
+    if let Some(id) = id {
+        let finished_at = get_current_timestamp() as i64;
+        if let Err(e) = update_refresh_run_outcome(
+            &id,
+            &finished_at,
+            convert_refresh_run_status_to_string(&status),
+            error.as_deref(),
+        ) {
+            log_database_error(&format!(
+                "Error while saving the outcome of refresh run {} : {:?}",
+                id, e
+            ));
+        }
+    }
+}
+
+// a failure to save the run is only logged, so that it does not prevent the refresh
+fn record_refresh_run(
+    started_at: &i64,
+    finished_at: Option<&i64>,
+    status: &RefreshRunStatus,
+) -> Option<i32> {
+    match add_refresh_run(
+        started_at,
+        finished_at,
+        convert_refresh_run_status_to_string(status),
+    ) {
+        Ok(id) => return Some(id),
+        Err(e) => {
+            log_database_error(&format!("Error while saving a refresh run : {:?}", e));
+            return None;
+        }
+    }
+}
+
+fn get_delay_before_next_run(refresh_schedule: &RefreshSchedule) -> Option<Duration> {
+    match refresh_schedule {
+        RefreshSchedule::Interval(interval) => return Some(*interval),
+        RefreshSchedule::Cron(schedule) => {
+            let next_run = schedule.upcoming(Local).next()?;
+            return Some((next_run - Local::now()).to_std().unwrap_or_default());
+        }
+    }
+}
+
+// REFRESH_CRON takes precedence over REFRESH_INTERVAL. An invalid expression is returned as a failure
+fn get_refresh_schedule() -> Result<Option<RefreshSchedule>, String> {
+    let refresh_cron = get_env_variable("REFRESH_CRON");
+    if !refresh_cron.trim().is_empty() {
+        let expression = convert_cron_expression(&refresh_cron);
+        match Schedule::from_str(&expression) {
+            Ok(schedule) => {
+                info!("Scheduler : refreshing all collections on {}", expression);
+                return Ok(Some(RefreshSchedule::Cron(Box::new(schedule))));
+            }
+            Err(e) => {
+                return Err(format!(
+                    "- REFRESH_CRON {} is not a valid cron expression : {}\n",
+                    refresh_cron, e
+                ));
+            }
+        }
+    }
+    let refresh_interval = get_env_variable("REFRESH_INTERVAL");
+    if refresh_interval.is_empty() {
+        return Ok(None);
+    }
+    match convert_string_to_u64(&refresh_interval) {
+        0 => return Ok(None),
+        refresh_interval => {
+            info!(
+                "Scheduler : refreshing all collections every {} seconds",
+                refresh_interval
+            );
+            return Ok(Some(RefreshSchedule::Interval(Duration::from_secs(
+                refresh_interval,
+            ))));
+        }
+    }
+}
+
+// the standard expressions, without the seconds, are run at the start of the minute. Their days of the week
+// go from 0 (sunday) to 7 (sunday again), where the cron crate numbers them from 1 (sunday) to 7 (saturday).
+// The expressions with the seconds are read with the syntax of the cron crate
+fn convert_cron_expression(refresh_cron: &str) -> String {
+    let mut fields = refresh_cron.split_whitespace().collect::<Vec<_>>();
+    if fields.len() != 5 {
+        return refresh_cron.trim().to_string();
+    }
+    let days_of_week = convert_standard_days_of_week(fields[4]);
+    fields[4] = &days_of_week;
+    return format!("0 {}", fields.join(" "));
+}
+
+// e.g. 1-5 becomes 2,3,4,5,6. The names and the values that are not valid are kept, the cron crate reads or rejects them
+fn convert_standard_days_of_week(days_of_week: &str) -> String {
+    return days_of_week
+        .split(',')
+        .map(|item| match get_standard_days(item) {
+            Some(days) => {
+                let mut days = days.into_iter().map(|day| day % 7 + 1).collect::<Vec<_>>();
+                days.sort();
+                days.dedup();
+                days.iter()
+                    .map(|day| day.to_string())
+                    .collect::<Vec<_>>()
+                    .join(",")
+            }
+            None => item.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+}
+
+// the days from 0 to 7 of an item of the day of week field, e.g. 3, 1-5, */2 or 1/2. None for * or a name
+fn get_standard_days(item: &str) -> Option<Vec<u64>> {
+    let (range, step) = match item.split_once('/') {
+        Some((range, step)) => (range, step.parse::<usize>().ok()?),
+        None => (item, 1),
+    };
+    let (start, end) = match range.split_once('-') {
+        Some((start, end)) => (start.parse::<u64>().ok()?, end.parse::<u64>().ok()?),
+        None if range == "*" && item.contains('/') => (0, 6),
+        // a step without range goes to the end of the week
+        None if item.contains('/') => (range.parse::<u64>().ok()?, 7),
+        None => {
+            let day = range.parse::<u64>().ok()?;
+            (day, day)
+        }
+    };
+    if step == 0 || start > end || end > 7 {
+        return None;
+    }
+    return Some((start..=end).step_by(step).collect::<Vec<_>>());
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Datelike, Utc, Weekday};
+
+    use super::*;
+
+    fn get_upcoming_weekdays(refresh_cron: &str) -> Vec<Weekday> {
+        let schedule = Schedule::from_str(&convert_cron_expression(refresh_cron)).unwrap();
+        let mut weekdays = schedule
+            .upcoming(Utc)
+            .take(14)
+            .map(|run| run.weekday())
+            .collect::<Vec<_>>();
+        weekdays.sort_by_key(|weekday| weekday.num_days_from_monday());
+        weekdays.dedup();
+        return weekdays;
+    }
+
+    #[test]
+    fn standard_days_of_week_start_on_sunday_at_zero() {
+        assert_eq!(get_upcoming_weekdays("0 3 * * 1"), vec![Weekday::Mon]);
+        assert_eq!(get_upcoming_weekdays("* * * * 0"), vec![Weekday::Sun]);
+        assert_eq!(get_upcoming_weekdays("0 3 * * 7"), vec![Weekday::Sun]);
+        assert_eq!(
+            get_upcoming_weekdays("0 3 * * 1-5"),
+            vec![
+                Weekday::Mon,
+                Weekday::Tue,
+                Weekday::Wed,
+                Weekday::Thu,
+                Weekday::Fri
+            ]
+        );
+        assert_eq!(
+            get_upcoming_weekdays("0 3 * * 5-7"),
+            vec![Weekday::Fri, Weekday::Sat, Weekday::Sun]
+        );
+        assert_eq!(
+            get_upcoming_weekdays("0 3 * * */3"),
+            vec![Weekday::Wed, Weekday::Sat, Weekday::Sun]
+        );
+        assert_eq!(get_upcoming_weekdays("0 3 * * SAT"), vec![Weekday::Sat]);
+    }
+
+    #[test]
+    fn expressions_with_seconds_are_kept() {
+        assert_eq!(convert_cron_expression(" 0 0 3 * * 2 "), "0 0 3 * * 2");
+        assert_eq!(get_upcoming_weekdays("0 0 3 * * 2"), vec![Weekday::Mon]);
+    }
+
+    #[test]
+    fn invalid_days_of_week_are_rejected() {
+        assert!(Schedule::from_str(&convert_cron_expression("0 3 * * 8")).is_err());
+        assert!(Schedule::from_str(&convert_cron_expression("0 3 * * 5-2")).is_err());
+    }
+}
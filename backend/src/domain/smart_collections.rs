@@ -0,0 +1,98 @@
+use super::{
+    collection_commons::{
+        create_new_playlist, get_collection_id_by_deezer_id, get_current_timestamp, get_playlists,
+        log_database_error, log_parameters_error,
+    },
+    controllers::{check_id_valid, check_smart_collection_rule_valid},
+    converter::{convert_collection_kind_to_string, convert_smart_collection_rule_to_string},
+    domain_models::{CollectionKind, SmartCollectionRule, Track},
+    errors::DomainError,
+    track_filters::partition_tracks,
+};
+use crate::infrastructure::database::{list_collections, update_collection_kind};
+
+const SECONDS_IN_A_DAY: u64 = 24 * 60 * 60;
+
+// creates a new deezer playlist, filled with the tracks matching the rule on refresh
+pub async fn create_smart_collection(
+    name: &str,
+    rule: SmartCollectionRule,
+) -> Result<bool, DomainError> {
+    log_parameters_error(check_smart_collection_rule_valid(&rule))?;
+    let id = create_new_playlist(name).await?;
+    return save_smart_collection_rule(&id.to_string(), &rule);
+}
+
+// turns an existing collection into a smart collection, or updates its rule
+pub fn set_smart_collection_rule(id: &str, rule: SmartCollectionRule) -> Result<bool, DomainError> {
+    log_parameters_error(check_id_valid(id.to_string()))?;
+    log_parameters_error(check_smart_collection_rule_valid(&rule))?;
+    get_collection_id_by_deezer_id(id)?;
+    return save_smart_collection_rule(id, &rule);
+}
+
+// returns the tracks of the source collections matching the rule
+pub async fn get_smart_collection_tracks(
+    id: &str,
+    rule: &SmartCollectionRule,
+) -> Result<Vec<Track>, DomainError> {
+    let mut smart_collection_tracks: Vec<Track> = Vec::new();
+    let min_time_add = rule
+        .added_within_days
+        .map(|days| get_current_timestamp().saturating_sub(days * SECONDS_IN_A_DAY));
+    let source_playlists = get_playlists(&get_source_collections_ids(id, rule)?).await?;
+    for source_playlist in source_playlists.into_iter() {
+        let (matching_tracks, _) = partition_tracks(&rule.filter, source_playlist.tracks)?;
+        for track in matching_tracks.into_iter() {
+            if min_time_add.map_or(true, |min_time_add| track.time_add >= min_time_add)
+                && !smart_collection_tracks
+                    .iter()
+                    .any(|el| el.deezer_id == track.deezer_id)
+            {
+                smart_collection_tracks.push(track);
+            }
+        }
+    }
+    return Ok(smart_collection_tracks);
+}
+
+fn get_source_collections_ids(
+    id: &str,
+    rule: &SmartCollectionRule,
+) -> Result<Vec<String>, DomainError> {
+    let source_collections_ids = match &rule.source_collections {
+        Some(source_collections) => source_collections.clone(),
+        None => match list_collections() {
+            Ok(collections) => collections
+                .into_iter()
+                .map(|collection| collection.deezer_id)
+                .collect::<Vec<_>>(),
+            Err(e) => {
+                return Err(log_database_error(&format!(
+                    "Error while listing the sources of smart collection {} : {:?}",
+                    id, e
+                )));
+            }
+        },
+    };
+    return Ok(source_collections_ids
+        .into_iter()
+        .filter(|source_id| source_id != id)
+        .collect::<Vec<_>>());
+}
+
+fn save_smart_collection_rule(id: &str, rule: &SmartCollectionRule) -> Result<bool, DomainError> {
+    match update_collection_kind(
+        id,
+        convert_collection_kind_to_string(&CollectionKind::Smart),
+        convert_smart_collection_rule_to_string(rule).as_deref(),
+    ) {
+        Ok(value) => return Ok(value),
+        Err(e) => {
+            return Err(log_database_error(&format!(
+                "Error while saving the rule of smart collection {} : {:?}",
+                id, e
+            )));
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use regex::Regex;
+
+use super::{
+    collection_commons::log_parameters_error,
+    domain_models::{Track, TrackFilter},
+    errors::DomainError,
+};
+
+// splits the tracks between the ones matching the filter and the others
+pub fn partition_tracks(
+    filter: &Option<TrackFilter>,
+    tracks: Vec<Track>,
+) -> Result<(Vec<Track>, Vec<Track>), DomainError> {
+    match filter {
+        Some(filter) => {
+            let title_regex = compile_title_regex(filter)?;
+            return Ok(tracks
+                .into_iter()
+                .partition(|track| track_matches_filter(filter, &title_regex, track)));
+        }
+        None => return Ok((tracks, Vec::new())),
+    }
+}
+
+// an artist value can either be the deezer artist id or the artist name (case insensitive)
+pub fn artist_matches(track: &Track, artist: &str) -> bool {
+    return artist.trim() == track.artist_id.to_string()
+        || artist.trim().to_lowercase() == track.artist.to_lowercase();
+}
+
+fn compile_title_regex(filter: &TrackFilter) -> Result<Option<Regex>, DomainError> {
+    match &filter.title_regex {
+        Some(title_regex) => match Regex::new(title_regex) {
+            Ok(regex) => return Ok(Some(regex)),
+            Err(e) => {
+                return log_parameters_error(format!(
+                    "- title_regex {} is not a valid regular expression : {}\n",
+                    title_regex, e
+                ))
+                .map(|_| None);
+            }
+        },
+        None => return Ok(None),
+    }
+}
+
+fn track_matches_filter(filter: &TrackFilter, title_regex: &Option<Regex>, track: &Track) -> bool {
+    if let Some(artists_in) = &filter.artists_in {
+        if !artists_in
+            .iter()
+            .any(|artist| artist_matches(track, artist))
+        {
+            return false;
+        }
+    }
+    if let Some(artists_not_in) = &filter.artists_not_in {
+        if artists_not_in
+            .iter()
+            .any(|artist| artist_matches(track, artist))
+        {
+            return false;
+        }
+    }
+    if filter.exclude_explicit.unwrap_or(false) && track.explicit {
+        return false;
+    }
+    if filter.min_duration.is_some_and(|min| track.duration < min)
+        || filter.max_duration.is_some_and(|max| track.duration > max)
+    {
+        return false;
+    }
+    if let Some(title_regex) = title_regex {
+        if !title_regex.is_match(&track.title) {
+            return false;
+        }
+    }
+    return true;
+}
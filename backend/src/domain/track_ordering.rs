@@ -0,0 +1,89 @@
+use std::cmp::Ordering;
+
+use super::{
+    collection_commons::{
+        convert_string_to_u64, get_collection_id_by_deezer_id, get_playlist, log_database_error,
+        log_deezer_error, log_parameters_error,
+    },
+    controllers::{check_id_valid, check_ordering_policy_valid},
+    converter::convert_track_order_to_string,
+    domain_models::{OrderingPolicy, Track, TrackOrder},
+    errors::DomainError,
+};
+use crate::infrastructure::{database::update_collection_ordering, deezer::order_playlist_tracks};
+
+pub fn set_ordering_policy(id: &str, ordering: OrderingPolicy) -> Result<bool, DomainError> {
+    log_parameters_error(check_id_valid(id.to_string()))?;
+    log_parameters_error(check_ordering_policy_valid(&ordering))?;
+    get_collection_id_by_deezer_id(id)?;
+    match update_collection_ordering(
+        id,
+        convert_track_order_to_string(&ordering.track_order),
+        &ordering.sort_whole_playlist,
+    ) {
+        Ok(value) => return Ok(value),
+        Err(e) => {
+            return Err(log_database_error(&format!(
+                "Error while saving the ordering policy of collection {} : {:?}",
+                id, e
+            )));
+        }
+    }
+}
+
+// stable sort, so the tracks grouped by child keep their order
+pub fn sort_tracks(tracks: &mut [Track], track_order: &TrackOrder) {
+    match track_order {
+        TrackOrder::GroupedByChild => {}
+        TrackOrder::TimeAdd => tracks.sort_by_key(|track| track.time_add),
+        TrackOrder::Artist => tracks.sort_by(|a, b| {
+            compare_ignoring_case(&a.artist, &b.artist)
+                .then_with(|| compare_ignoring_case(&a.title, &b.title))
+        }),
+        TrackOrder::Title => tracks.sort_by(|a, b| compare_ignoring_case(&a.title, &b.title)),
+    }
+}
+
+fn compare_ignoring_case(a: &str, b: &str) -> Ordering {
+    return a.to_lowercase().cmp(&b.to_lowercase());
+}
+
+// reorders the collection on deezer once the tracks are added, following the ordering policy
+pub async fn apply_ordering_policy(
+    id: &str,
+    ordering: &OrderingPolicy,
+    added_tracks: &[Track],
+) -> Result<(), DomainError> {
+    if ordering.track_order == TrackOrder::GroupedByChild || added_tracks.is_empty() {
+        return Ok(());
+    }
+    let playlist = get_playlist(&convert_string_to_u64(id)).await?;
+    let mut ordered_tracks = playlist
+        .tracks
+        .into_iter()
+        .filter(|track| {
+            !added_tracks
+                .iter()
+                .any(|added_track| added_track.deezer_id == track.deezer_id)
+        })
+        .collect::<Vec<_>>();
+    let mut sorted_added_tracks = added_tracks.to_vec();
+    sort_tracks(&mut sorted_added_tracks, &ordering.track_order);
+    ordered_tracks.append(&mut sorted_added_tracks);
+    if ordering.sort_whole_playlist {
+        sort_tracks(&mut ordered_tracks, &ordering.track_order);
+    }
+    let ordered_tracks_ids = ordered_tracks
+        .into_iter()
+        .map(|track| track.deezer_id)
+        .collect::<Vec<_>>();
+    match order_playlist_tracks(id, ordered_tracks_ids).await {
+        Ok(_) => return Ok(()),
+        Err(e) => {
+            return Err(log_deezer_error(&format!(
+                "Error while ordering the tracks of the playlist {} : {:?}",
+                id, e
+            )));
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use serde::Serialize;
+
+use super::{
+    collection_commons::{convert_string_to_u64, escape_xml, get_playlist},
+    collection_management::{
+        get_collection, get_collection_tracks_excluding_children,
+        get_refresh_report_and_track_count,
+    },
+    domain_models::{Collection, Track, TracklistFormat, TracklistView},
+    errors::DomainError,
+};
+
+// JSPF playlist, as described on https://xspf.org/jspf
+#[derive(Serialize)]
+struct JspfDocument<'a> {
+    playlist: JspfPlaylist<'a>,
+}
+
+#[derive(Serialize)]
+struct JspfPlaylist<'a> {
+    title: &'a str,
+    identifier: &'a str,
+    track: Vec<JspfTrack<'a>>,
+}
+
+#[derive(Serialize)]
+struct JspfTrack<'a> {
+    location: Vec<&'a str>,
+    title: &'a str,
+    creator: &'a str,
+    duration: u64, // in milliseconds
+}
+
+// renders the tracks of the collection, for a backup or an import in another player
+pub async fn export_collection_tracklist(
+    id: &str,
+    format: &TracklistFormat,
+    view: &TracklistView,
+) -> Result<String, DomainError> {
+    let collection = get_collection(id).await?;
+    let tracks = match view {
+        TracklistView::Flattened => {
+            // the tracks of the children are filtered and deduplicated as in a refresh
+            let (refresh_report, _) = get_refresh_report_and_track_count(id).await?;
+            let mut tracks = get_playlist(&convert_string_to_u64(id)).await?.tracks;
+            tracks.extend(refresh_report.tracks_to_add);
+            tracks
+        }
+        TracklistView::Own => get_collection_tracks_excluding_children(id).await?,
+    };
+    match format {
+        TracklistFormat::M3u => return Ok(render_m3u(&collection, &tracks)),
+        TracklistFormat::Xspf => return Ok(render_xspf(&collection, &tracks)),
+        TracklistFormat::Jspf => return Ok(render_jspf(&collection, &tracks)),
+        TracklistFormat::Csv => return Ok(render_csv(&tracks)),
+    }
+}
+
+pub fn get_tracklist_file_extension(format: &TracklistFormat) -> &'static str {
+    match format {
+        TracklistFormat::M3u => return "m3u",
+        TracklistFormat::Xspf => return "xspf",
+        TracklistFormat::Jspf => return "jspf",
+        TracklistFormat::Csv => return "csv",
+    }
+}
+
+fn render_m3u(collection: &Collection, tracks: &[Track]) -> String {
+    // the line breaks would end the directives
+    let escape = |value: &str| value.replace(['\r', '\n'], " ");
+    let mut m3u = format!("#EXTM3U\n#PLAYLIST:{}\n", escape(&collection.name));
+    for track in tracks.iter() {
+        m3u.push_str(&format!(
+            "#EXTINF:{},{} - {}\n{}\n",
+            track.duration,
+            escape(&track.artist),
+            escape(&track.title),
+            track.link
+        ));
+    }
+    return m3u;
+}
+
+fn render_xspf(collection: &Collection, tracks: &[Track]) -> String {
+    let mut xspf = format!(
+        concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<playlist version=\"1\" xmlns=\"http://xspf.org/ns/0/\">\n",
+            "  <title>{}</title>\n",
+            "  <identifier>{}</identifier>\n",
+            "  <trackList>\n"
+        ),
+        escape_xml(&collection.name),
+        escape_xml(&collection.url)
+    );
+    for track in tracks.iter() {
+        xspf.push_str(&format!(
+            concat!(
+                "    <track>\n",
+                "      <location>{}</location>\n",
+                "      <title>{}</title>\n",
+                "      <creator>{}</creator>\n",
+                "      <duration>{}</duration>\n",
+                "    </track>\n"
+            ),
+            escape_xml(&track.link),
+            escape_xml(&track.title),
+            escape_xml(&track.artist),
+            track.duration * 1000
+        ));
+    }
+    xspf.push_str("  </trackList>\n</playlist>\n");
+    return xspf;
+}
+
+fn render_jspf(collection: &Collection, tracks: &[Track]) -> String {
+    let document = JspfDocument {
+        playlist: JspfPlaylist {
+            title: &collection.name,
+            identifier: &collection.url,
+            track: tracks
+                .iter()
+                .map(|track| JspfTrack {
+                    location: vec![&track.link],
+                    title: &track.title,
+                    creator: &track.artist,
+                    duration: track.duration * 1000,
+                })
+                .collect::<Vec<_>>(),
+        },
+    };
+    // the document only holds strings and numbers, so it can always be serialized
+    return serde_json::to_string_pretty(&document).unwrap_or_default();
+}
+
+fn render_csv(tracks: &[Track]) -> String {
+    // the fields containing a separator, a quote or a line break are quoted, as in RFC 4180
+    let escape = |value: &str| {
+        if value.contains([',', '"', '\r', '\n']) {
+            return format!("\"{}\"", value.replace('"', "\"\""));
+        }
+        return value.to_string();
+    };
+    let mut csv = String::from("title,artist,link,duration\r\n");
+    for track in tracks.iter() {
+        csv.push_str(&format!(
+            "{},{},{},{}\r\n",
+            escape(&track.title),
+            escape(&track.artist),
+            escape(&track.link),
+            track.duration
+        ));
+    }
+    return csv;
+}
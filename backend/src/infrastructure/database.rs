@@ -1,15 +1,27 @@
 use crate::common::common::get_env_variable;
-use crate::infrastructure::database_converter::convert_collection_list_model_to_database;
+use crate::infrastructure::database_converter::{
+    convert_collection_list_model_to_database, convert_collection_model_to_database,
+};
 use crate::infrastructure::database_models::CollectionDatabase;
 use backend::models::*;
 use backend::schema::collection_dependencies;
+use backend::schema::collection_exclusions;
+use backend::schema::collection_tags;
 use backend::schema::collections;
+use backend::schema::refresh_runs;
+use backend::schema::track_isrcs;
 use diesel::prelude::*;
+use diesel::sqlite::Sqlite;
 use diesel::SqliteConnection;
 use log::error;
 use log::info;
 
-use super::database_models::InitCollectionDatabase;
+use super::database_models::{
+    ChildCollectionDatabase, CollectionDependencyDatabase, CollectionListFilterDatabase,
+    CollectionSortDatabase, CollectionTagDatabase, DanglingDependencyDatabase,
+    DependencyMutationDatabase, ExclusionDatabase, InitCollectionDatabase, RefreshOutcomeDatabase,
+    RefreshRunDatabase, TrackIsrcDatabase,
+};
 use super::errors::DatabaseError;
 
 pub fn init_collection(options: InitCollectionDatabase) -> Result<usize, DatabaseError> {
@@ -17,12 +29,7 @@ pub fn init_collection(options: InitCollectionDatabase) -> Result<usize, Databas
         "Database : initializing collection {} to database",
         options.url.as_str()
     );
-    match create_collection(
-        &mut get_connection()?,
-        options.name.as_str(),
-        options.deezer_id.as_str(),
-        options.url.as_str(),
-    ) {
+    match create_collection(&mut get_connection()?, &options) {
         Ok(res_size) => Ok(res_size),
         Err(e) => {
             return Err(log_result_error(&format!(
@@ -67,6 +74,67 @@ pub fn list_collections() -> Result<Vec<CollectionDatabase>, DatabaseError> {
     }
 }
 
+// returns the page of the collections matching the filter, and the number of matching collections
+pub fn list_collections_filtered(
+    filter: &CollectionListFilterDatabase,
+) -> Result<(Vec<CollectionDatabase>, i64), DatabaseError> {
+    info!("Database : listing collections matching {:?}", filter);
+    let connection = &mut get_connection()?;
+    let total_count = match filter_collections(filter)
+        .count()
+        .get_result::<i64>(connection)
+    {
+        Ok(total_count) => total_count,
+        Err(e) => {
+            return Err(log_result_error(&format!(
+                "Error trying to count the collections : {:?}",
+                e
+            )));
+        }
+    };
+    let mut query = filter_collections(filter);
+    query = match (&filter.sort, filter.descending) {
+        (Some(CollectionSortDatabase::Name), false) => query.order_by(collections::name.asc()),
+        (Some(CollectionSortDatabase::Name), true) => query.order_by(collections::name.desc()),
+        (Some(CollectionSortDatabase::CreatedAt), false) => {
+            query.order_by(collections::created_at.asc())
+        }
+        (Some(CollectionSortDatabase::CreatedAt), true) => {
+            query.order_by(collections::created_at.desc())
+        }
+        (Some(CollectionSortDatabase::LastRefreshedAt), false) => {
+            query.order_by(collections::last_refreshed_at.asc())
+        }
+        (Some(CollectionSortDatabase::LastRefreshedAt), true) => {
+            query.order_by(collections::last_refreshed_at.desc())
+        }
+        (None, false) => query.order_by(collections::id.asc()),
+        (None, true) => query.order_by(collections::id.desc()),
+    };
+    // the id keeps the pages stable between collections having the same sort value
+    query = query.then_order_by(collections::id.asc());
+    if let Some(limit) = filter.limit {
+        query = query.limit(limit);
+    }
+    if let Some(offset) = filter.offset {
+        query = query.offset(offset);
+    }
+    match query.select(Collection::as_select()).load(connection) {
+        Ok(results) => {
+            return Ok((
+                convert_collection_list_model_to_database(results),
+                total_count,
+            ));
+        }
+        Err(e) => {
+            return Err(log_result_error(&format!(
+                "Error trying to load the collections : {:?}",
+                e
+            )));
+        }
+    }
+}
+
 pub fn get_collection(deezer_id: &str) -> Result<CollectionDatabase, DatabaseError> {
     info!(
         "Database : getting collection with tracks from deezer id : {}",
@@ -78,11 +146,7 @@ pub fn get_collection(deezer_id: &str) -> Result<CollectionDatabase, DatabaseErr
         .get_result(&mut get_connection()?)
     {
         Ok(collection) => {
-            return Ok(CollectionDatabase {
-                deezer_id: collection.deezer_id,
-                url: collection.url,
-                name: collection.name,
-            });
+            return Ok(convert_collection_model_to_database(collection));
         }
         Err(e) => {
             return Err(log_result_error(&format!(
@@ -93,11 +157,16 @@ pub fn get_collection(deezer_id: &str) -> Result<CollectionDatabase, DatabaseErr
     }
 }
 
-pub fn add_collection_to_parent(parent_id: &i32, child_id: &i32) -> Result<bool, DatabaseError> {
+pub fn add_collection_to_parent(
+    parent_id: &i32,
+    child_id: &i32,
+    filter: Option<&str>,
+) -> Result<bool, DatabaseError> {
     info!("Database : adding collection {} to {}", child_id, parent_id);
     let collection_dependency = NewCollectionDependency {
-        parent_id: parent_id,
-        child_id: child_id,
+        parent_id,
+        child_id,
+        filter,
     };
     match diesel::insert_into(collection_dependencies::table)
         .values(&collection_dependency)
@@ -123,26 +192,185 @@ pub fn get_child_collections(parent_id: &i32) -> Result<Vec<CollectionDatabase>,
         .filter(collection_dependencies::parent_id.eq(parent_id))
         .select(Collection::as_select())
         .get_results(&mut get_connection()?)
+    {
+        Ok(collections) => {
+            return Ok(convert_collection_list_model_to_database(collections));
+        }
+        Err(e) => {
+            return Err(log_result_error(&format!(
+                "Error getting child collections of {} : {:?}",
+                parent_id, e
+            )));
+        }
+    }
+}
+
+pub fn get_child_collection_dependencies(
+    parent_id: &i32,
+) -> Result<Vec<ChildCollectionDatabase>, DatabaseError> {
+    info!(
+        "Database : getting child collections with filters of {}",
+        parent_id
+    );
+    match collection_dependencies::table
+        .inner_join(collections::table.on(collections::id.eq(collection_dependencies::child_id)))
+        .filter(collection_dependencies::parent_id.eq(parent_id))
+        .select((Collection::as_select(), collection_dependencies::filter))
+        .get_results::<(Collection, Option<String>)>(&mut get_connection()?)
     {
         Ok(collections) => {
             return Ok(collections
                 .into_iter()
-                .map(|collection| CollectionDatabase {
+                .map(|(collection, filter)| ChildCollectionDatabase {
                     deezer_id: collection.deezer_id,
                     url: collection.url,
                     name: collection.name,
+                    filter,
                 })
                 .collect::<Vec<_>>());
         }
         Err(e) => {
             return Err(log_result_error(&format!(
-                "Error getting child collections of {} : {:?}",
+                "Error getting child collections with filters of {} : {:?}",
                 parent_id, e
             )));
         }
     }
 }
 
+pub fn update_collection_dependency_filter(
+    parent_id: &i32,
+    child_id: &i32,
+    filter: Option<&str>,
+) -> Result<bool, DatabaseError> {
+    info!(
+        "Database : updating filter of child collection {} in {}",
+        child_id, parent_id
+    );
+    match diesel::update(
+        collection_dependencies::table.filter(
+            collection_dependencies::parent_id
+                .eq(parent_id)
+                .and(collection_dependencies::child_id.eq(child_id)),
+        ),
+    )
+    .set(collection_dependencies::filter.eq(filter))
+    .execute(&mut get_connection()?)
+    {
+        Ok(updated) => return Ok(updated > 0),
+        Err(e) => {
+            return Err(log_result_error(&format!(
+                "Error updating filter of child collection {} in {} : {:?}",
+                child_id, parent_id, e
+            )));
+        }
+    }
+}
+
+pub fn update_collection_kind(
+    deezer_id: &str,
+    kind: &str,
+    rule: Option<&str>,
+) -> Result<bool, DatabaseError> {
+    info!(
+        "Database : updating kind of collection {} to {}",
+        deezer_id, kind
+    );
+    match diesel::update(collections::table.filter(collections::deezer_id.eq(deezer_id)))
+        .set((collections::kind.eq(kind), collections::rule.eq(rule)))
+        .execute(&mut get_connection()?)
+    {
+        Ok(updated) => return Ok(updated > 0),
+        Err(e) => {
+            return Err(log_result_error(&format!(
+                "Error updating kind of collection {} : {:?}",
+                deezer_id, e
+            )));
+        }
+    }
+}
+
+pub fn update_collection_name_and_url(
+    deezer_id: &str,
+    name: &str,
+    url: &str,
+) -> Result<bool, DatabaseError> {
+    info!(
+        "Database : updating name of collection {} to {} and url to {}",
+        deezer_id, name, url
+    );
+    match diesel::update(collections::table.filter(collections::deezer_id.eq(deezer_id)))
+        .set((collections::name.eq(name), collections::url.eq(url)))
+        .execute(&mut get_connection()?)
+    {
+        Ok(updated) => return Ok(updated > 0),
+        Err(e) => {
+            return Err(log_result_error(&format!(
+                "Error updating name and url of collection {} : {:?}",
+                deezer_id, e
+            )));
+        }
+    }
+}
+
+pub fn update_collection_ordering(
+    deezer_id: &str,
+    track_order: &str,
+    sort_whole_playlist: &bool,
+) -> Result<bool, DatabaseError> {
+    info!(
+        "Database : updating track order of collection {} to {}",
+        deezer_id, track_order
+    );
+    match diesel::update(collections::table.filter(collections::deezer_id.eq(deezer_id)))
+        .set((
+            collections::track_order.eq(track_order),
+            collections::sort_whole_playlist.eq(sort_whole_playlist),
+        ))
+        .execute(&mut get_connection()?)
+    {
+        Ok(updated) => return Ok(updated > 0),
+        Err(e) => {
+            return Err(log_result_error(&format!(
+                "Error updating track order of collection {} : {:?}",
+                deezer_id, e
+            )));
+        }
+    }
+}
+
+pub fn update_collection_refresh_policy(
+    deezer_id: &str,
+    refresh_enabled: &bool,
+    refresh_schedule: &str,
+) -> Result<bool, DatabaseError> {
+    info!(
+        "Database : updating refresh policy of collection {} to {} ({})",
+        deezer_id,
+        refresh_schedule,
+        if *refresh_enabled {
+            "enabled"
+        } else {
+            "disabled"
+        }
+    );
+    match diesel::update(collections::table.filter(collections::deezer_id.eq(deezer_id)))
+        .set((
+            collections::refresh_enabled.eq(refresh_enabled),
+            collections::refresh_schedule.eq(refresh_schedule),
+        ))
+        .execute(&mut get_connection()?)
+    {
+        Ok(updated) => return Ok(updated > 0),
+        Err(e) => {
+            return Err(log_result_error(&format!(
+                "Error updating refresh policy of collection {} : {:?}",
+                deezer_id, e
+            )));
+        }
+    }
+}
+
 pub fn remove_collection_to_parent(parent_id: &i32, child_id: &i32) -> Result<bool, DatabaseError> {
     info!(
         "Database : removing child collection {} from {}",
@@ -167,6 +395,208 @@ pub fn remove_collection_to_parent(parent_id: &i32, child_id: &i32) -> Result<bo
     }
 }
 
+// reads the collection deezer ids and the dependencies, then applies in order the mutations planned from them
+// in one transaction, so that no other write happens between the check of the plan and its application.
+// Nothing is applied if the plan returns None or if one mutation fails. Returns whether the mutations were applied
+pub fn apply_planned_dependency_mutations<F>(plan: F) -> Result<bool, DatabaseError>
+where
+    F: FnOnce(
+        Vec<String>,
+        Vec<CollectionDependencyDatabase>,
+    ) -> Option<Vec<DependencyMutationDatabase>>,
+{
+    info!("Database : applying planned dependency mutations");
+    // the write lock is taken before reading, so that the plan is checked against the state it updates
+    let result = get_connection()?.immediate_transaction(|connection| {
+        let collections_ids = collections::table
+            .select(collections::deezer_id)
+            .load::<String>(connection)?;
+        let dependencies = load_collection_dependencies(connection)?;
+        let mutations = match plan(collections_ids, dependencies) {
+            Some(mutations) => mutations,
+            None => return diesel::result::QueryResult::Ok(false),
+        };
+        info!(
+            "Database : applying {} dependency mutations",
+            mutations.len()
+        );
+        for mutation in mutations.iter() {
+            match mutation {
+                DependencyMutationDatabase::Add {
+                    parent_deezer_id,
+                    child_deezer_id,
+                    filter,
+                } => {
+                    let parent_id = get_collection_id_in_transaction(connection, parent_deezer_id)?;
+                    let child_id = get_collection_id_in_transaction(connection, child_deezer_id)?;
+                    diesel::insert_into(collection_dependencies::table)
+                        .values(&NewCollectionDependency {
+                            parent_id: &parent_id,
+                            child_id: &child_id,
+                            filter: filter.as_deref(),
+                        })
+                        .execute(connection)?;
+                }
+                DependencyMutationDatabase::Remove {
+                    parent_deezer_id,
+                    child_deezer_id,
+                } => {
+                    let parent_id = get_collection_id_in_transaction(connection, parent_deezer_id)?;
+                    let child_id = get_collection_id_in_transaction(connection, child_deezer_id)?;
+                    diesel::delete(
+                        collection_dependencies::table.filter(
+                            collection_dependencies::parent_id
+                                .eq(parent_id)
+                                .and(collection_dependencies::child_id.eq(child_id)),
+                        ),
+                    )
+                    .execute(connection)?;
+                }
+            }
+        }
+        diesel::result::QueryResult::Ok(true)
+    });
+    match result {
+        Ok(applied) => return Ok(applied),
+        Err(e) => {
+            return Err(log_result_error(&format!(
+                "Error applying the planned dependency mutations : {:?}",
+                e
+            )));
+        }
+    }
+}
+
+// returns every dependency between two existing collections
+pub fn get_collection_dependencies() -> Result<Vec<CollectionDependencyDatabase>, DatabaseError> {
+    info!("Database : getting collection dependencies");
+    match load_collection_dependencies(&mut get_connection()?) {
+        Ok(dependencies) => return Ok(dependencies),
+        Err(e) => {
+            return Err(log_result_error(&format!(
+                "Error getting collection dependencies : {:?}",
+                e
+            )));
+        }
+    }
+}
+
+// returns the dependencies whose parent or child is missing in the collections
+pub fn get_dangling_dependencies() -> Result<Vec<DanglingDependencyDatabase>, DatabaseError> {
+    info!("Database : getting dangling collection dependencies");
+    let connection = &mut get_connection()?;
+    let dependencies = match collection_dependencies::table
+        .select((
+            collection_dependencies::id,
+            collection_dependencies::parent_id,
+            collection_dependencies::child_id,
+        ))
+        .load::<(i32, i32, i32)>(connection)
+    {
+        Ok(dependencies) => dependencies,
+        Err(e) => {
+            return Err(log_result_error(&format!(
+                "Error getting collection dependencies : {:?}",
+                e
+            )));
+        }
+    };
+    let collections_ids = match collections::table
+        .select((collections::id, collections::deezer_id))
+        .load::<(i32, String)>(connection)
+    {
+        Ok(collections_ids) => collections_ids,
+        Err(e) => {
+            return Err(log_result_error(&format!(
+                "Error getting collection ids : {:?}",
+                e
+            )));
+        }
+    };
+    let get_deezer_id = |collection_id: i32| {
+        collections_ids
+            .iter()
+            .find(|(id, _)| *id == collection_id)
+            .map(|(_, deezer_id)| deezer_id.clone())
+    };
+    return Ok(dependencies
+        .into_iter()
+        .map(|(id, parent_id, child_id)| DanglingDependencyDatabase {
+            id,
+            parent_deezer_id: get_deezer_id(parent_id),
+            child_deezer_id: get_deezer_id(child_id),
+        })
+        .filter(|dependency| {
+            dependency.parent_deezer_id.is_none() || dependency.child_deezer_id.is_none()
+        })
+        .collect::<Vec<_>>());
+}
+
+pub fn remove_collection_dependency_by_id(dependency_id: &i32) -> Result<bool, DatabaseError> {
+    info!(
+        "Database : removing collection dependency {}",
+        dependency_id
+    );
+    match diesel::delete(
+        collection_dependencies::table.filter(collection_dependencies::id.eq(dependency_id)),
+    )
+    .execute(&mut get_connection()?)
+    {
+        Ok(removed) => return Ok(removed > 0),
+        Err(e) => {
+            return Err(log_result_error(&format!(
+                "Error removing collection dependency {} : {:?}",
+                dependency_id, e
+            )));
+        }
+    }
+}
+
+pub fn update_collection_owner(
+    deezer_id: &str,
+    owner_id: &str,
+    origin: &str,
+) -> Result<bool, DatabaseError> {
+    info!(
+        "Database : updating owner of collection {} to {} ({})",
+        deezer_id, owner_id, origin
+    );
+    match diesel::update(collections::table.filter(collections::deezer_id.eq(deezer_id)))
+        .set((
+            collections::owner_id.eq(owner_id),
+            collections::origin.eq(origin),
+        ))
+        .execute(&mut get_connection()?)
+    {
+        Ok(updated) => return Ok(updated > 0),
+        Err(e) => {
+            return Err(log_result_error(&format!(
+                "Error updating owner of collection {} : {:?}",
+                deezer_id, e
+            )));
+        }
+    }
+}
+
+pub fn update_collection_checksum(deezer_id: &str, checksum: &str) -> Result<bool, DatabaseError> {
+    info!(
+        "Database : updating checksum of collection {} to {}",
+        deezer_id, checksum
+    );
+    match diesel::update(collections::table.filter(collections::deezer_id.eq(deezer_id)))
+        .set(collections::checksum.eq(checksum))
+        .execute(&mut get_connection()?)
+    {
+        Ok(updated) => return Ok(updated > 0),
+        Err(e) => {
+            return Err(log_result_error(&format!(
+                "Error updating checksum of collection {} : {:?}",
+                deezer_id, e
+            )));
+        }
+    }
+}
+
 pub fn remove_collection_in_database(collection_id: &i32) -> Result<bool, DatabaseError> {
     let mut res = true;
     let connection = &mut get_connection()?;
@@ -200,6 +630,32 @@ pub fn remove_collection_in_database(collection_id: &i32) -> Result<bool, Databa
             )));
         }
     };
+    match diesel::delete(
+        collection_exclusions::table.filter(collection_exclusions::collection_id.eq(collection_id)),
+    )
+    .execute(connection)
+    {
+        Ok(_) => res &= true,
+        Err(e) => {
+            return Err(log_result_error(&format!(
+                "Error removing collection exclusions of {} from the database {:?}",
+                collection_id, e,
+            )));
+        }
+    };
+    match diesel::delete(
+        collection_tags::table.filter(collection_tags::collection_id.eq(collection_id)),
+    )
+    .execute(connection)
+    {
+        Ok(_) => res &= true,
+        Err(e) => {
+            return Err(log_result_error(&format!(
+                "Error removing collection tags of {} from the database {:?}",
+                collection_id, e,
+            )));
+        }
+    };
     match diesel::delete(collections::table.filter(collections::id.eq(collection_id)))
         .execute(connection)
     {
@@ -239,21 +695,402 @@ pub fn clear_database() -> Result<bool, DatabaseError> {
             )));
         }
     };
+    match diesel::delete(collection_exclusions::table).execute(connection) {
+        Ok(_) => {
+            info!("Database : collection exclusions cleared");
+        }
+        Err(e) => {
+            return Err(log_result_error(&format!(
+                "Error clearing collections exclusions from the database : {:?}",
+                e
+            )));
+        }
+    };
+    match diesel::delete(collection_tags::table).execute(connection) {
+        Ok(_) => {
+            info!("Database : collection tags cleared");
+        }
+        Err(e) => {
+            return Err(log_result_error(&format!(
+                "Error clearing collections tags from the database : {:?}",
+                e
+            )));
+        }
+    };
+    match diesel::delete(track_isrcs::table).execute(connection) {
+        Ok(_) => {
+            info!("Database : track isrcs cleared");
+        }
+        Err(e) => {
+            return Err(log_result_error(&format!(
+                "Error clearing track isrcs from the database : {:?}",
+                e
+            )));
+        }
+    };
+    match diesel::delete(refresh_runs::table).execute(connection) {
+        Ok(_) => {
+            info!("Database : refresh runs cleared");
+        }
+        Err(e) => {
+            return Err(log_result_error(&format!(
+                "Error clearing refresh runs from the database : {:?}",
+                e
+            )));
+        }
+    };
     return Ok(true);
 }
 
+pub fn add_collection_exclusion(
+    collection_id: &i32,
+    kind: &str,
+    value: &str,
+) -> Result<bool, DatabaseError> {
+    info!(
+        "Database : adding exclusion {} {} to collection {}",
+        kind, value, collection_id
+    );
+    let collection_exclusion = NewCollectionExclusion {
+        collection_id,
+        kind,
+        value,
+    };
+    match diesel::insert_into(collection_exclusions::table)
+        .values(&collection_exclusion)
+        .on_conflict_do_nothing()
+        .execute(&mut get_connection()?)
+    {
+        Ok(_) => {
+            return Ok(true);
+        }
+        Err(e) => {
+            return Err(log_result_error(&format!(
+                "Error adding exclusion {} {} to collection {} : {:?}",
+                kind, value, collection_id, e
+            )));
+        }
+    }
+}
+
+pub fn remove_collection_exclusion(
+    collection_id: &i32,
+    kind: &str,
+    value: &str,
+) -> Result<bool, DatabaseError> {
+    info!(
+        "Database : removing exclusion {} {} from collection {}",
+        kind, value, collection_id
+    );
+    match diesel::delete(
+        collection_exclusions::table.filter(
+            collection_exclusions::collection_id
+                .eq(collection_id)
+                .and(collection_exclusions::kind.eq(kind))
+                .and(collection_exclusions::value.eq(value)),
+        ),
+    )
+    .execute(&mut get_connection()?)
+    {
+        Ok(_) => return Ok(true),
+        Err(e) => {
+            return Err(log_result_error(&format!(
+                "Error removing exclusion {} {} from collection {} : {:?}",
+                kind, value, collection_id, e
+            )));
+        }
+    }
+}
+
+pub fn get_collection_exclusions(
+    collection_id: &i32,
+) -> Result<Vec<ExclusionDatabase>, DatabaseError> {
+    info!(
+        "Database : getting exclusions of collection {}",
+        collection_id
+    );
+    match collection_exclusions::table
+        .filter(collection_exclusions::collection_id.eq(collection_id))
+        .select(CollectionExclusion::as_select())
+        .get_results(&mut get_connection()?)
+    {
+        Ok(exclusions) => {
+            return Ok(exclusions
+                .into_iter()
+                .map(|exclusion| ExclusionDatabase {
+                    kind: exclusion.kind,
+                    value: exclusion.value,
+                })
+                .collect::<Vec<_>>());
+        }
+        Err(e) => {
+            return Err(log_result_error(&format!(
+                "Error getting exclusions of collection {} : {:?}",
+                collection_id, e
+            )));
+        }
+    }
+}
+
+pub fn update_collection_description(
+    deezer_id: &str,
+    description: Option<&str>,
+) -> Result<bool, DatabaseError> {
+    info!(
+        "Database : updating description of collection {}",
+        deezer_id
+    );
+    match diesel::update(collections::table.filter(collections::deezer_id.eq(deezer_id)))
+        .set(collections::description.eq(description))
+        .execute(&mut get_connection()?)
+    {
+        Ok(updated) => return Ok(updated > 0),
+        Err(e) => {
+            return Err(log_result_error(&format!(
+                "Error updating description of collection {} : {:?}",
+                deezer_id, e
+            )));
+        }
+    }
+}
+
+pub fn update_collection_refresh_outcome(
+    deezer_id: &str,
+    outcome: RefreshOutcomeDatabase,
+) -> Result<bool, DatabaseError> {
+    info!(
+        "Database : saving refresh outcome {} of collection {}",
+        outcome.status, deezer_id
+    );
+    let connection = &mut get_connection()?;
+    let collection_filter = collections::table.filter(collections::deezer_id.eq(deezer_id));
+    let updated = match outcome.track_count {
+        // the last track count is kept when the refresh failed
+        Some(track_count) => diesel::update(collection_filter)
+            .set((
+                collections::last_refreshed_at.eq(outcome.refreshed_at),
+                collections::last_refresh_status.eq(&outcome.status),
+                collections::last_refresh_error.eq(&outcome.error),
+                collections::last_refresh_tracks_added.eq(outcome.tracks_added),
+                collections::track_count.eq(track_count),
+            ))
+            .execute(connection),
+        None => diesel::update(collection_filter)
+            .set((
+                collections::last_refreshed_at.eq(outcome.refreshed_at),
+                collections::last_refresh_status.eq(&outcome.status),
+                collections::last_refresh_error.eq(&outcome.error),
+                collections::last_refresh_tracks_added.eq(outcome.tracks_added),
+            ))
+            .execute(connection),
+    };
+    match updated {
+        Ok(updated) => return Ok(updated > 0),
+        Err(e) => {
+            return Err(log_result_error(&format!(
+                "Error saving refresh outcome of collection {} : {:?}",
+                deezer_id, e
+            )));
+        }
+    }
+}
+
+// replaces every tag of the collection
+pub fn set_collection_tags(collection_id: &i32, tags: &[String]) -> Result<bool, DatabaseError> {
+    info!(
+        "Database : setting tags {:?} of collection {}",
+        tags, collection_id
+    );
+    let result = get_connection()?.transaction(|connection| {
+        diesel::delete(
+            collection_tags::table.filter(collection_tags::collection_id.eq(collection_id)),
+        )
+        .execute(connection)?;
+        for tag in tags.iter() {
+            diesel::insert_into(collection_tags::table)
+                .values(&NewCollectionTag { collection_id, tag })
+                .on_conflict_do_nothing()
+                .execute(connection)?;
+        }
+        diesel::result::QueryResult::Ok(())
+    });
+    match result {
+        Ok(_) => return Ok(true),
+        Err(e) => {
+            return Err(log_result_error(&format!(
+                "Error setting tags of collection {} : {:?}",
+                collection_id, e
+            )));
+        }
+    }
+}
+
+pub fn get_collections_tags() -> Result<Vec<CollectionTagDatabase>, DatabaseError> {
+    info!("Database : getting tags of the collections");
+    match collection_tags::table
+        .inner_join(collections::table)
+        .select((collections::deezer_id, collection_tags::tag))
+        .order_by(collection_tags::tag)
+        .load::<(String, String)>(&mut get_connection()?)
+    {
+        Ok(tags) => {
+            return Ok(tags
+                .into_iter()
+                .map(|(deezer_id, tag)| CollectionTagDatabase { deezer_id, tag })
+                .collect::<Vec<_>>());
+        }
+        Err(e) => {
+            return Err(log_result_error(&format!(
+                "Error getting tags of the collections : {:?}",
+                e
+            )));
+        }
+    }
+}
+
+pub fn add_track_isrc(deezer_id: &str, isrc: &str) -> Result<bool, DatabaseError> {
+    info!("Database : saving isrc {} of track {}", isrc, deezer_id);
+    let track_isrc = NewTrackIsrc { deezer_id, isrc };
+    match diesel::insert_into(track_isrcs::table)
+        .values(&track_isrc)
+        .on_conflict_do_nothing()
+        .execute(&mut get_connection()?)
+    {
+        Ok(_) => return Ok(true),
+        Err(e) => {
+            return Err(log_result_error(&format!(
+                "Error saving isrc {} of track {} : {:?}",
+                isrc, deezer_id, e
+            )));
+        }
+    }
+}
+
+pub fn get_track_isrcs(deezer_ids: &[String]) -> Result<Vec<TrackIsrcDatabase>, DatabaseError> {
+    info!("Database : getting isrcs of {} tracks", deezer_ids.len());
+    match track_isrcs::table
+        .filter(track_isrcs::deezer_id.eq_any(deezer_ids))
+        .select(TrackIsrc::as_select())
+        .get_results(&mut get_connection()?)
+    {
+        Ok(track_isrcs) => {
+            return Ok(track_isrcs
+                .into_iter()
+                .map(|track_isrc| TrackIsrcDatabase {
+                    deezer_id: track_isrc.deezer_id,
+                    isrc: track_isrc.isrc,
+                })
+                .collect::<Vec<_>>());
+        }
+        Err(e) => {
+            return Err(log_result_error(&format!(
+                "Error getting isrcs of tracks : {:?}",
+                e
+            )));
+        }
+    }
+}
+
+// saves a run, and returns its id
+pub fn add_refresh_run(
+    started_at: &i64,
+    finished_at: Option<&i64>,
+    status: &str,
+) -> Result<i32, DatabaseError> {
+    info!("Database : saving refresh run started at {}", started_at);
+    let result = get_connection()?.transaction(|connection| {
+        diesel::insert_into(refresh_runs::table)
+            .values(&NewRefreshRun {
+                started_at,
+                finished_at,
+                status,
+            })
+            .execute(connection)?;
+        refresh_runs::table
+            .select(refresh_runs::id)
+            .order_by(refresh_runs::id.desc())
+            .first::<i32>(connection)
+    });
+    match result {
+        Ok(id) => return Ok(id),
+        Err(e) => {
+            return Err(log_result_error(&format!(
+                "Error saving refresh run started at {} : {:?}",
+                started_at, e
+            )));
+        }
+    }
+}
+
+pub fn update_refresh_run_outcome(
+    id: &i32,
+    finished_at: &i64,
+    status: &str,
+    error: Option<&str>,
+) -> Result<bool, DatabaseError> {
+    info!("Database : saving outcome {} of refresh run {}", status, id);
+    match diesel::update(refresh_runs::table.filter(refresh_runs::id.eq(id)))
+        .set((
+            refresh_runs::finished_at.eq(finished_at),
+            refresh_runs::status.eq(status),
+            refresh_runs::error.eq(error),
+        ))
+        .execute(&mut get_connection()?)
+    {
+        Ok(updated) => return Ok(updated > 0),
+        Err(e) => {
+            return Err(log_result_error(&format!(
+                "Error saving outcome of refresh run {} : {:?}",
+                id, e
+            )));
+        }
+    }
+}
+
+// returns the last runs, the most recent first
+pub fn list_refresh_runs(limit: i64) -> Result<Vec<RefreshRunDatabase>, DatabaseError> {
+    info!("Database : listing the last {} refresh runs", limit);
+    match refresh_runs::table
+        .select(RefreshRun::as_select())
+        .order_by(refresh_runs::id.desc())
+        .limit(limit)
+        .load(&mut get_connection()?)
+    {
+        Ok(runs) => {
+            return Ok(runs
+                .into_iter()
+                .map(|run| RefreshRunDatabase {
+                    id: run.id,
+                    started_at: run.started_at,
+                    finished_at: run.finished_at,
+                    status: run.status,
+                    error: run.error,
+                })
+                .collect::<Vec<_>>());
+        }
+        Err(e) => {
+            return Err(log_result_error(&format!(
+                "Error listing refresh runs : {:?}",
+                e
+            )));
+        }
+    }
+}
+
 fn create_collection(
     conn: &mut SqliteConnection,
-    name: &str,
-    deezer_id: &str,
-    url: &str,
+    options: &InitCollectionDatabase,
 ) -> Result<usize, diesel::result::Error> {
     let new_collection = NewCollection {
-        name,
-        deezer_id,
-        url,
+        name: options.name.as_str(),
+        deezer_id: options.deezer_id.as_str(),
+        url: options.url.as_str(),
+        created_at: &options.created_at,
+        track_count: &options.track_count,
+        origin: options.origin.as_str(),
+        owner_id: options.owner_id.as_deref(),
     };
-    info!("Database : saving collection {} to database", &url);
+    info!("Database : saving collection {} to database", &options.url);
     diesel::insert_into(collections::table)
         .values(&new_collection)
         .on_conflict_do_nothing()
@@ -262,7 +1099,7 @@ fn create_collection(
 
 fn establish_connection() -> Result<SqliteConnection, ConnectionError> {
     let database_url: String = get_env_variable("DATABASE_URL");
-    SqliteConnection::establish(&(database_url.as_str()))
+    SqliteConnection::establish(database_url.as_str())
 }
 
 fn get_connection() -> Result<SqliteConnection, DatabaseError> {
@@ -275,6 +1112,74 @@ fn get_connection() -> Result<SqliteConnection, DatabaseError> {
     }
 }
 
+fn filter_collections(
+    filter: &CollectionListFilterDatabase,
+) -> collections::BoxedQuery<'_, Sqlite> {
+    let mut query = collections::table.into_boxed();
+    if let Some(search) = &filter.search {
+        // the wildcards typed by the user are searched as is
+        let search = search
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_");
+        query = query.filter(collections::name.like(format!("%{}%", search)).escape('\\'));
+    }
+    if let Some(tag) = &filter.tag {
+        query = query.filter(
+            collections::id.eq_any(
+                collection_tags::table
+                    .filter(collection_tags::tag.eq(tag))
+                    .select(collection_tags::collection_id),
+            ),
+        );
+    }
+    return query;
+}
+
+fn load_collection_dependencies(
+    connection: &mut SqliteConnection,
+) -> Result<Vec<CollectionDependencyDatabase>, diesel::result::Error> {
+    let (parents, children) = diesel::alias!(collections as parents, collections as children);
+    let dependencies = collection_dependencies::table
+        .inner_join(
+            parents.on(parents
+                .field(collections::id)
+                .eq(collection_dependencies::parent_id)),
+        )
+        .inner_join(
+            children.on(children
+                .field(collections::id)
+                .eq(collection_dependencies::child_id)),
+        )
+        .select((
+            parents.field(collections::deezer_id),
+            children.field(collections::deezer_id),
+            collection_dependencies::filter,
+        ))
+        .order_by(collection_dependencies::id)
+        .load::<(String, String, Option<String>)>(connection)?;
+    return Ok(dependencies
+        .into_iter()
+        .map(
+            |(parent_deezer_id, child_deezer_id, filter)| CollectionDependencyDatabase {
+                parent_deezer_id,
+                child_deezer_id,
+                filter,
+            },
+        )
+        .collect::<Vec<_>>());
+}
+
+fn get_collection_id_in_transaction(
+    connection: &mut SqliteConnection,
+    deezer_id: &str,
+) -> Result<i32, diesel::result::Error> {
+    collections::table
+        .filter(collections::deezer_id.eq(deezer_id))
+        .select(collections::id)
+        .first::<i32>(connection)
+}
+
 fn load_collections(
     connection: &mut SqliteConnection,
 ) -> Result<Vec<Collection>, diesel::result::Error> {
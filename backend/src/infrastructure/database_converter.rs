@@ -6,15 +6,31 @@ pub fn convert_collection_list_model_to_database(
 ) -> Vec<CollectionDatabase> {
     collection_model_list
         .into_iter()
-        .map(|collection| convert_collection_model_to_database(collection))
+        .map(convert_collection_model_to_database)
         .collect::<Vec<_>>()
 }
 
-fn convert_collection_model_to_database(collection_model: Collection) -> CollectionDatabase {
+pub fn convert_collection_model_to_database(collection_model: Collection) -> CollectionDatabase {
     let collection_database = CollectionDatabase {
         name: collection_model.name,
         deezer_id: collection_model.deezer_id,
         url: collection_model.url,
+        kind: collection_model.kind,
+        rule: collection_model.rule,
+        track_order: collection_model.track_order,
+        sort_whole_playlist: collection_model.sort_whole_playlist,
+        description: collection_model.description,
+        created_at: collection_model.created_at,
+        last_refreshed_at: collection_model.last_refreshed_at,
+        last_refresh_status: collection_model.last_refresh_status,
+        last_refresh_error: collection_model.last_refresh_error,
+        last_refresh_tracks_added: collection_model.last_refresh_tracks_added,
+        track_count: collection_model.track_count,
+        origin: collection_model.origin,
+        owner_id: collection_model.owner_id,
+        checksum: collection_model.checksum,
+        refresh_enabled: collection_model.refresh_enabled,
+        refresh_schedule: collection_model.refresh_schedule,
     };
     return collection_database;
 }
@@ -5,6 +5,10 @@ pub struct InitCollectionDatabase {
     pub name: String,
     pub deezer_id: String,
     pub url: String,
+    pub created_at: i64,
+    pub track_count: i32,
+    pub origin: String,
+    pub owner_id: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -12,12 +16,110 @@ pub struct CollectionDatabase {
     pub deezer_id: String,
     pub url: String,
     pub name: String,
+    pub kind: String,
+    pub rule: Option<String>,
+    pub track_order: String,
+    pub sort_whole_playlist: bool,
+    pub description: Option<String>,
+    pub created_at: Option<i64>,
+    pub last_refreshed_at: Option<i64>,
+    pub last_refresh_status: Option<String>,
+    pub last_refresh_error: Option<String>,
+    pub last_refresh_tracks_added: Option<i32>,
+    pub track_count: Option<i32>,
+    pub origin: String,
+    pub owner_id: Option<String>,
+    pub checksum: Option<String>, // last checksum of the playlist seen by the watcher
+    pub refresh_enabled: bool,
+    pub refresh_schedule: String,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
-pub struct TrackDatabase {
+pub struct ChildCollectionDatabase {
     pub deezer_id: String,
-    pub title: String,
-    pub artist: String,
     pub url: String,
+    pub name: String,
+    pub filter: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ExclusionDatabase {
+    pub kind: String,
+    pub value: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct TrackIsrcDatabase {
+    pub deezer_id: String,
+    pub isrc: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct CollectionTagDatabase {
+    pub deezer_id: String,
+    pub tag: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct RefreshOutcomeDatabase {
+    pub refreshed_at: i64,
+    pub status: String,
+    pub error: Option<String>,
+    pub tracks_added: Option<i32>,
+    pub track_count: Option<i32>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct RefreshRunDatabase {
+    pub id: i32,
+    pub started_at: i64,
+    pub finished_at: Option<i64>,
+    pub status: String,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct CollectionDependencyDatabase {
+    pub parent_deezer_id: String,
+    pub child_deezer_id: String,
+    pub filter: Option<String>,
+}
+
+// a change of a batch of dependency mutations, with the deezer ids of the collections
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub enum DependencyMutationDatabase {
+    Add {
+        parent_deezer_id: String,
+        child_deezer_id: String,
+        filter: Option<String>,
+    },
+    Remove {
+        parent_deezer_id: String,
+        child_deezer_id: String,
+    },
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct DanglingDependencyDatabase {
+    pub id: i32,
+    pub parent_deezer_id: Option<String>, // not set if the parent is missing
+    pub child_deezer_id: Option<String>,  // not set if the child is missing
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub enum CollectionSortDatabase {
+    Name,
+    CreatedAt,
+    LastRefreshedAt,
+}
+
+// filters, sorts and paginates the collections in the query
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct CollectionListFilterDatabase {
+    pub tag: Option<String>,
+    pub search: Option<String>,               // substring of the name
+    pub sort: Option<CollectionSortDatabase>, // by id if not set
+    pub descending: bool,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
 }
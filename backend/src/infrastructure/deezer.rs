@@ -1,25 +1,71 @@
-use std::time::Duration;
+use std::{sync::OnceLock, time::Duration};
 
 use deezer::models::Playlist;
 use log::warn;
 use ratelimit::Ratelimiter;
-use reqwest::Error;
+use reqwest::{redirect, Client, Error, RequestBuilder, Response};
 
 use crate::common::common::get_env_variable;
 
-use super::deezer_models::CreatedPlaylist;
+use super::deezer_models::{
+    CreatedPlaylist, PlaylistSummaryResponse, TrackIsrcResponse, TrackSearchPage, UserPlaylistsPage,
+};
 
 const PATH_PLAYLIST: &str = "playlist";
 const PATH_TRACKS: &str = "tracks";
+const PATH_TRACK: &str = "track";
 const PATH_USER: &str = "user";
 const PATH_PLAYLISTS: &str = "playlists";
+const PATH_SEARCH: &str = "search";
+const TRACK_SEARCH_LIMIT: u64 = 5;
+const USER_PLAYLISTS_PAGE_SIZE: u64 = 100;
+const DEFAULT_SHORT_LINK_PATH: &str = "https://deezer.page.link";
+const DEFAULT_USER_AGENT: &str = concat!("CollectionManager/", env!("CARGO_PKG_VERSION"));
+const DEFAULT_CONNECT_TIMEOUT: u64 = 5;
+const DEFAULT_READ_TIMEOUT: u64 = 30;
+const DEFAULT_MAX_RETRIES: u64 = 2;
+// doubled after each retry, up to MAX_RETRY_BACKOFF_MILLIS
+const RETRY_BACKOFF_MILLIS: u64 = 500;
+const MAX_RETRY_BACKOFF_MILLIS: u64 = 60_000;
 
-static mut RATELIMITER: Option<Ratelimiter> = None;
+// read once at startup by init_deezer_config, so that an invalid value stops the server instead of failing each query
+static DEEZER_CONFIG: OnceLock<DeezerConfig> = OnceLock::new();
+// shared by all the queries, so that the connections to deezer are reused
+static DEEZER_CLIENT: OnceLock<Client> = OnceLock::new();
+static SHORT_LINK_CLIENT: OnceLock<Client> = OnceLock::new();
+
+struct DeezerConfig {
+    connect_timeout: u64, // seconds
+    read_timeout: u64,    // seconds
+    max_retries: u64,
+    ratelimiter: Ratelimiter,
+}
+
+// a request that may have reached deezer is only sent again if sending it twice has the same effect
+enum RetryPolicy {
+    Idempotent,
+    NotIdempotent,
+}
+
+// the short links are not served by the deezer api, so they do not use its rate limit
+enum RateLimit {
+    DeezerApi,
+    Unlimited,
+}
 
 fn get_deezer_api_path() -> String {
     return get_env_variable("DEEZER_API_URL");
 }
 
+// base url of the short links, e.g. https://deezer.page.link
+fn get_short_link_path() -> String {
+    let short_link_path = get_env_variable("DEEZER_SHORT_LINK_URL");
+    if short_link_path.is_empty() {
+        return DEFAULT_SHORT_LINK_PATH.to_string();
+    }
+    return short_link_path;
+}
+
 fn get_user_id() -> String {
     return get_env_variable("DEEZER_USER_ID");
 }
@@ -28,65 +74,203 @@ fn get_token() -> String {
     return get_env_variable("DEEZER_API_TOKEN");
 }
 
-fn string_to_u64(to_convert: &str) -> u64 {
-    return to_convert.parse::<u64>().unwrap();
+// checks and saves the settings of the deezer queries, returns the invalid ones
+pub fn init_deezer_config() -> Result<bool, String> {
+    let mut failures = String::new();
+    let connect_timeout = read_u64_setting(
+        "DEEZER_CONNECT_TIMEOUT",
+        Some(DEFAULT_CONNECT_TIMEOUT),
+        &mut failures,
+    );
+    let read_timeout = read_u64_setting(
+        "DEEZER_READ_TIMEOUT",
+        Some(DEFAULT_READ_TIMEOUT),
+        &mut failures,
+    );
+    let max_retries = read_u64_setting(
+        "DEEZER_MAX_RETRIES",
+        Some(DEFAULT_MAX_RETRIES),
+        &mut failures,
+    );
+    let rate_limit_amount = read_u64_setting("RATE_LIMIT", None, &mut failures);
+    let rate_limit_timeout = read_u64_setting("RATE_LIMIT_TIMEOUT", None, &mut failures);
+    if !failures.is_empty() {
+        return Err(failures);
+    }
+    let ratelimiter =
+        match Ratelimiter::builder(rate_limit_amount, Duration::from_secs(rate_limit_timeout))
+            .max_tokens(rate_limit_amount)
+            .build()
+        {
+            Ok(ratelimiter) => ratelimiter,
+            Err(e) => {
+                return Err(format!(
+                    "- RATE_LIMIT {} and RATE_LIMIT_TIMEOUT {} are not a valid rate limit : {:?}\n",
+                    rate_limit_amount, rate_limit_timeout, e
+                ));
+            }
+        };
+    let _ = DEEZER_CONFIG.set(DeezerConfig {
+        connect_timeout,
+        read_timeout,
+        max_retries,
+        ratelimiter,
+    });
+    return Ok(true);
+}
+
+// the setting is required if there is no default value
+fn read_u64_setting(name: &str, default: Option<u64>, failures: &mut String) -> u64 {
+    let value = get_env_variable(name);
+    match (value.trim().parse::<u64>(), default) {
+        (Ok(value), _) => return value,
+        (Err(_), Some(default)) if value.is_empty() => return default,
+        (Err(_), _) => {
+            failures.push_str(&format!(
+                "- {} {:?} is not a valid number\n",
+                name, value
+            ));
+            return 0;
+        }
+    }
+}
+
+fn get_deezer_config() -> &'static DeezerConfig {
+    return DEEZER_CONFIG
+        .get()
+        .expect("init_deezer_config must be called at startup");
 }
 
-fn init_rate_limiter() {
-    let rate_limit_amount = string_to_u64(get_env_variable("RATE_LIMIT").as_str());
-    let rate_limit_timeout = string_to_u64(get_env_variable("RATE_LIMIT_TIMEOUT").as_str());
-    unsafe {
-        RATELIMITER = Some(
-            Ratelimiter::builder(rate_limit_amount, Duration::from_secs(rate_limit_timeout))
-                .max_tokens(rate_limit_amount)
-                .build()
-                .unwrap(),
-        );
-    };
-}
-
-fn limit_rate_if_needed() {
-    unsafe {
-        match &RATELIMITER {
-            Some(ratelimit) => {
-                if let Err(sleep) = ratelimit.try_wait() {
-                    warn!("Too many requests to the deezer API, limiting the rates.");
-                    std::thread::sleep(sleep);
-                    return;
-                }
-            }
-            None => init_rate_limiter(),
+fn get_user_agent() -> String {
+    let user_agent = get_env_variable("DEEZER_USER_AGENT");
+    if user_agent.is_empty() {
+        return DEFAULT_USER_AGENT.to_string();
+    }
+    return user_agent;
+}
+
+// the timeouts are in seconds, the read timeout applies to each read and not to the whole response
+fn build_client(redirect_policy: redirect::Policy) -> Client {
+    return Client::builder()
+        .user_agent(get_user_agent())
+        .connect_timeout(Duration::from_secs(get_deezer_config().connect_timeout))
+        .read_timeout(Duration::from_secs(get_deezer_config().read_timeout))
+        .redirect(redirect_policy)
+        .build()
+        .unwrap();
+}
+
+fn get_deezer_client() -> &'static Client {
+    return DEEZER_CLIENT.get_or_init(|| build_client(redirect::Policy::default()));
+}
+
+// the short links must not be followed, their target is read from the location header
+fn get_short_link_client() -> &'static Client {
+    return SHORT_LINK_CLIENT.get_or_init(|| build_client(redirect::Policy::none()));
+}
+
+// sleeps without blocking the worker thread, which the watcher task shares with the requests.
+// the concurrent requests all wait for a token, so that they can not go through together after the same sleep
+async fn limit_rate_if_needed() {
+    let ratelimit = &get_deezer_config().ratelimiter;
+    while let Err(sleep) = ratelimit.try_wait() {
+        warn!("Too many requests to the deezer API, limiting the rates.");
+        tokio::time::sleep(sleep).await;
+    }
+}
+
+// each attempt goes through the rate limiter. The last response or error is returned once the retries are exhausted
+async fn send_with_retries(
+    request: RequestBuilder,
+    retry_policy: RetryPolicy,
+) -> Result<Response, Error> {
+    return send_with_retries_limited(request, retry_policy, RateLimit::DeezerApi).await;
+}
+
+async fn send_with_retries_limited(
+    request: RequestBuilder,
+    retry_policy: RetryPolicy,
+    rate_limit: RateLimit,
+) -> Result<Response, Error> {
+    let max_retries = get_deezer_config().max_retries;
+    let mut retries: u64 = 0;
+    loop {
+        if let RateLimit::DeezerApi = rate_limit {
+            limit_rate_if_needed().await;
+        }
+        // only the requests with a streamed body can not be cloned, none is sent to deezer
+        let Some(attempt) = request.try_clone() else {
+            return request.send().await;
+        };
+        let result = attempt.send().await;
+        if retries >= max_retries || !is_retryable(&result, &retry_policy) {
+            return result;
+        }
+        let backoff = Duration::from_millis(get_retry_backoff_millis(retries));
+        retries += 1;
+        match &result {
+            Ok(response) => warn!(
+                "Deezer API answered {}, retrying in {:?} ({}/{})",
+                response.status(),
+                backoff,
+                retries,
+                max_retries
+            ),
+            Err(e) => warn!(
+                "Error querying the deezer API : {}, retrying in {:?} ({}/{})",
+                e, backoff, retries, max_retries
+            ),
+        }
+        tokio::time::sleep(backoff).await;
+    }
+}
+
+// saturates instead of overflowing when DEEZER_MAX_RETRIES is large
+fn get_retry_backoff_millis(retries: u64) -> u64 {
+    let factor = 2u64.saturating_pow(u32::try_from(retries).unwrap_or(u32::MAX));
+    return RETRY_BACKOFF_MILLIS
+        .saturating_mul(factor)
+        .min(MAX_RETRY_BACKOFF_MILLIS);
+}
+
+fn is_retryable(result: &Result<Response, Error>, retry_policy: &RetryPolicy) -> bool {
+    match (result, retry_policy) {
+        (Ok(response), RetryPolicy::Idempotent) => return response.status().is_server_error(),
+        (Err(e), RetryPolicy::Idempotent) => {
+            return e.is_connect() || e.is_timeout() || e.is_request()
         }
+        // the request may have been handled by deezer, unless the connection could not be opened
+        (Ok(_), RetryPolicy::NotIdempotent) => return false,
+        (Err(e), RetryPolicy::NotIdempotent) => return e.is_connect(),
     }
 }
 
 pub async fn create_playlist(name: &str) -> Result<u64, Error> {
     let mut url: String = format!(
-        "{}/{}/{}/{}?{}",
+        "{}/{}/{}/{}?title={}",
         get_deezer_api_path(),
         PATH_USER,
         get_user_id(),
         PATH_PLAYLISTS,
-        format!("title={}", name)
+        name
     );
     let token = get_token();
-    if token != "" {
+    if !token.is_empty() {
         url = format!("{}&access_token={}", url, token);
     }
-    limit_rate_if_needed();
-    let client = reqwest::Client::new();
-    let response: reqwest::Response = client.post(url).header("content-length", 0).send().await?;
+    let request = get_deezer_client().post(url).header("content-length", 0);
+    let response = send_with_retries(request, RetryPolicy::NotIdempotent).await?;
     match response.json::<CreatedPlaylist>().await {
         Ok(playlist) => Ok(playlist.id),
         Err(e) => {
             eprintln!(
-                "Error querying {}/{}/{}/{}?{}&{} : {}",
+                "Error querying {}/{}/{}/{}?title={}&access_token={} : {}",
                 get_deezer_api_path(),
                 PATH_USER,
                 get_user_id(),
                 PATH_PLAYLISTS,
-                format!("title={}", name),
-                format!("access_token={}", get_token()),
+                name,
+                get_token(),
                 e
             );
             Err(e)
@@ -94,6 +278,40 @@ pub async fn create_playlist(name: &str) -> Result<u64, Error> {
     }
 }
 
+// returns the page of the playlists of the user starting at index
+pub async fn get_user_playlists(index: &u64) -> Result<UserPlaylistsPage, Error> {
+    let mut url: String = format!(
+        "{}/{}/{}/{}?index={}&limit={}",
+        get_deezer_api_path(),
+        PATH_USER,
+        get_user_id(),
+        PATH_PLAYLISTS,
+        index,
+        USER_PLAYLISTS_PAGE_SIZE
+    );
+    let token = get_token();
+    if !token.is_empty() {
+        url = format!("{}&access_token={}", url, token);
+    }
+    let request = get_deezer_client().get(url);
+    let response = send_with_retries(request, RetryPolicy::Idempotent).await?;
+    match response.json::<UserPlaylistsPage>().await {
+        Ok(page) => return Ok(page),
+        Err(e) => {
+            eprintln!(
+                "Error querying {}/{}/{}/{}?index={} : {}",
+                get_deezer_api_path(),
+                PATH_USER,
+                get_user_id(),
+                PATH_PLAYLISTS,
+                index,
+                e
+            );
+            return Err(e);
+        }
+    }
+}
+
 pub async fn get_playlist(deezer_playlist_id: &u64) -> Result<Playlist, Error> {
     let mut url: String = format!(
         "{}/{}/{}",
@@ -102,12 +320,11 @@ pub async fn get_playlist(deezer_playlist_id: &u64) -> Result<Playlist, Error> {
         deezer_playlist_id,
     );
     let token = get_token();
-    if token != "" {
+    if !token.is_empty() {
         url = format!("{}?access_token={}", url, token);
     }
-    limit_rate_if_needed();
-    let client = reqwest::Client::new();
-    let response = client.get(url).send().await?;
+    let request = get_deezer_client().get(url);
+    let response = send_with_retries(request, RetryPolicy::Idempotent).await?;
     match response.json::<deezer::models::Playlist>().await {
         Ok(playlist) => Ok(playlist),
         Err(e) => {
@@ -123,45 +340,130 @@ pub async fn get_playlist(deezer_playlist_id: &u64) -> Result<Playlist, Error> {
     }
 }
 
+pub async fn update_playlist_title(playlist_id: &str, title: &str) -> Result<bool, Error> {
+    let mut url: String = format!(
+        "{}/{}/{}",
+        get_deezer_api_path(),
+        PATH_PLAYLIST,
+        playlist_id
+    );
+    let token = get_token();
+    if !token.is_empty() {
+        url = format!("{}?access_token={}", url, token);
+    }
+    let request = get_deezer_client()
+        .post(url)
+        .query(&[("title", title)])
+        .header("content-length", 0);
+    let response = send_with_retries(request, RetryPolicy::Idempotent).await?;
+    match response.json::<bool>().await {
+        Ok(value) => return Ok(value),
+        Err(e) => {
+            eprintln!(
+                "Error querying {}/{}/{}?title={} : {}",
+                get_deezer_api_path(),
+                PATH_PLAYLIST,
+                playlist_id,
+                title,
+                e
+            );
+            return Err(e);
+        }
+    }
+}
+
+pub async fn delete_playlist(playlist_id: &str) -> Result<bool, Error> {
+    let mut url: String = format!(
+        "{}/{}/{}",
+        get_deezer_api_path(),
+        PATH_PLAYLIST,
+        playlist_id
+    );
+    let token = get_token();
+    if !token.is_empty() {
+        url = format!("{}?access_token={}", url, token);
+    }
+    let request = get_deezer_client().delete(url);
+    let response = send_with_retries(request, RetryPolicy::Idempotent).await?;
+    match response.json::<bool>().await {
+        Ok(value) => return Ok(value),
+        Err(e) => {
+            eprintln!(
+                "Error querying DELETE {}/{}/{} : {}",
+                get_deezer_api_path(),
+                PATH_PLAYLIST,
+                playlist_id,
+                e
+            );
+            return Err(e);
+        }
+    }
+}
+
+// unlike get_playlist, returns the deezer error instead of failing when the playlist can not be read
+pub async fn get_playlist_summary(playlist_id: &str) -> Result<PlaylistSummaryResponse, Error> {
+    let mut url: String = format!(
+        "{}/{}/{}",
+        get_deezer_api_path(),
+        PATH_PLAYLIST,
+        playlist_id
+    );
+    let token = get_token();
+    if !token.is_empty() {
+        url = format!("{}?access_token={}", url, token);
+    }
+    let request = get_deezer_client().get(url);
+    let response = send_with_retries(request, RetryPolicy::Idempotent).await?;
+    match response.json::<PlaylistSummaryResponse>().await {
+        Ok(playlist) => return Ok(playlist),
+        Err(e) => {
+            eprintln!(
+                "Error querying {}/{}/{} : {}",
+                get_deezer_api_path(),
+                PATH_PLAYLIST,
+                playlist_id,
+                e
+            );
+            return Err(e);
+        }
+    }
+}
+
 pub async fn add_tracks_to_playlist(
     playlist_id: &str,
     track_ids: Vec<String>,
 ) -> Result<bool, Error> {
-    if track_ids.len() > 0 {
+    if !track_ids.is_empty() {
         let mut track_ids_query_params: String = "".to_owned();
         for track_id in track_ids.into_iter() {
             track_ids_query_params.push_str(&format!("{},", track_id));
         }
         track_ids_query_params.pop(); // removes the last comma
         let mut url: String = format!(
-            "{}/{}/{}/{}?{}",
+            "{}/{}/{}/{}?songs={}",
             get_deezer_api_path(),
             PATH_PLAYLIST,
             playlist_id,
             PATH_TRACKS,
-            format!("songs={}", track_ids_query_params)
+            track_ids_query_params
         );
         let token = get_token();
-        if token != "" {
+        if !token.is_empty() {
             url = format!("{}&access_token={}", url, token);
         }
-        limit_rate_if_needed();
-        let client = reqwest::Client::new();
-        let response = client.post(url).header("content-length", 0).send().await?;
+        let request = get_deezer_client().post(url).header("content-length", 0);
+        let response = send_with_retries(request, RetryPolicy::NotIdempotent).await?;
         match response.json::<bool>().await {
             Ok(value) => return Ok(value),
             Err(e) => {
                 eprintln!(
-                    "Error querying {}/{}/{}/{}?{} : {}",
+                    "Error querying {}/{}/{}/{}?access_token={}&songs={} : {}",
                     get_deezer_api_path(),
                     PATH_PLAYLIST,
                     playlist_id,
                     PATH_TRACKS,
-                    format!(
-                        "access_token={}&songs={}",
-                        get_token(),
-                        track_ids_query_params
-                    ),
+                    get_token(),
+                    track_ids_query_params,
                     e
                 );
                 return Err(e);
@@ -170,3 +472,142 @@ pub async fn add_tracks_to_playlist(
     }
     return Ok(false);
 }
+
+pub async fn order_playlist_tracks(
+    playlist_id: &str,
+    track_ids: Vec<String>,
+) -> Result<bool, Error> {
+    let mut url: String = format!(
+        "{}/{}/{}/{}?order={}",
+        get_deezer_api_path(),
+        PATH_PLAYLIST,
+        playlist_id,
+        PATH_TRACKS,
+        track_ids.join(",")
+    );
+    let token = get_token();
+    if !token.is_empty() {
+        url = format!("{}&access_token={}", url, token);
+    }
+    let request = get_deezer_client().post(url).header("content-length", 0);
+    let response = send_with_retries(request, RetryPolicy::Idempotent).await?;
+    match response.json::<bool>().await {
+        Ok(value) => return Ok(value),
+        Err(e) => {
+            eprintln!(
+                "Error querying {}/{}/{}/{}?order={} : {}",
+                get_deezer_api_path(),
+                PATH_PLAYLIST,
+                playlist_id,
+                PATH_TRACKS,
+                track_ids.join(","),
+                e
+            );
+            return Err(e);
+        }
+    }
+}
+
+// returns an empty isrc when deezer has none for the track, e.g. when the track is not available anymore
+pub async fn get_track_isrc(deezer_track_id: &str) -> Result<String, Error> {
+    let mut url: String = format!(
+        "{}/{}/{}",
+        get_deezer_api_path(),
+        PATH_TRACK,
+        deezer_track_id,
+    );
+    let token = get_token();
+    if !token.is_empty() {
+        url = format!("{}?access_token={}", url, token);
+    }
+    let request = get_deezer_client().get(url);
+    let response = send_with_retries(request, RetryPolicy::Idempotent).await?;
+    match response.json::<TrackIsrcResponse>().await {
+        Ok(TrackIsrcResponse::Track(track)) => Ok(track.isrc),
+        Ok(TrackIsrcResponse::Error(_)) => Ok(String::new()),
+        Err(e) => {
+            eprintln!(
+                "Error querying {}/{}/{} : {}",
+                get_deezer_api_path(),
+                PATH_TRACK,
+                deezer_track_id,
+                e
+            );
+            Err(e)
+        }
+    }
+}
+
+// unlike get_track_isrc, returns the deezer error instead of failing when no track has this isrc
+pub async fn get_track_by_isrc(isrc: &str) -> Result<TrackIsrcResponse, Error> {
+    let mut url: String = format!("{}/{}/isrc:{}", get_deezer_api_path(), PATH_TRACK, isrc,);
+    let token = get_token();
+    if !token.is_empty() {
+        url = format!("{}?access_token={}", url, token);
+    }
+    let request = get_deezer_client().get(url);
+    let response = send_with_retries(request, RetryPolicy::Idempotent).await?;
+    match response.json::<TrackIsrcResponse>().await {
+        Ok(track) => return Ok(track),
+        Err(e) => {
+            eprintln!(
+                "Error querying {}/{}/isrc:{} : {}",
+                get_deezer_api_path(),
+                PATH_TRACK,
+                isrc,
+                e
+            );
+            return Err(e);
+        }
+    }
+}
+
+// query uses the deezer advanced search syntax, e.g. artist:"name" track:"title"
+pub async fn search_tracks(query: &str) -> Result<TrackSearchPage, Error> {
+    let mut url: String = format!(
+        "{}/{}/{}?limit={}",
+        get_deezer_api_path(),
+        PATH_SEARCH,
+        PATH_TRACK,
+        TRACK_SEARCH_LIMIT
+    );
+    let token = get_token();
+    if !token.is_empty() {
+        url = format!("{}&access_token={}", url, token);
+    }
+    let request = get_deezer_client().get(url).query(&[("q", query)]);
+    let response = send_with_retries(request, RetryPolicy::Idempotent).await?;
+    match response.json::<TrackSearchPage>().await {
+        Ok(page) => return Ok(page),
+        Err(e) => {
+            eprintln!(
+                "Error querying {}/{}/{}?q={} : {}",
+                get_deezer_api_path(),
+                PATH_SEARCH,
+                PATH_TRACK,
+                query,
+                e
+            );
+            return Err(e);
+        }
+    }
+}
+
+// returns the url the short link redirects to, without following it
+pub async fn resolve_short_link(path: &str) -> Result<Option<String>, Error> {
+    let url: String = format!("{}/{}", get_short_link_path(), path);
+    let request = get_short_link_client().get(&url);
+    match send_with_retries_limited(request, RetryPolicy::Idempotent, RateLimit::Unlimited).await {
+        Ok(response) => {
+            return Ok(response
+                .headers()
+                .get(reqwest::header::LOCATION)
+                .and_then(|location| location.to_str().ok())
+                .map(|location| location.to_string()));
+        }
+        Err(e) => {
+            eprintln!("Error querying {} : {}", url, e);
+            return Err(e);
+        }
+    }
+}
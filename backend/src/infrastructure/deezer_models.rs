@@ -5,20 +5,84 @@ pub struct CreatedPlaylist {
     pub id: u64,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
-pub struct Playlist {
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct TrackIsrc {
+    pub id: u64,
+    #[serde(default)]
+    pub isrc: String,
+}
+
+// the fields of a playlist needed to check that it is still available, or that its tracks changed
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct PlaylistSummary {
     pub id: u64,
     pub title: String,
     pub public: bool,
-    pub nb_tracks: u64,
-    pub url: String,
-    pub tracks: Vec<Track>,
+    pub link: String,
+    pub creator: PlaylistCreator,
+    #[serde(default)]
+    pub checksum: String,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
-pub struct Track {
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct PlaylistCreator {
+    pub id: u64,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct DeezerError {
+    #[serde(rename = "type", default)]
+    pub error_type: String,
+    #[serde(default)]
+    pub message: String,
+    #[serde(default)]
+    pub code: u64,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct DeezerErrorResponse {
+    pub error: DeezerError,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum PlaylistSummaryResponse {
+    Error(DeezerErrorResponse),
+    Playlist(PlaylistSummary),
+}
+
+// a page of /user/{id}/playlists. next is only set when there are more playlists to fetch
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct UserPlaylistsPage {
+    #[serde(default)]
+    pub data: Vec<PlaylistSummary>,
+    #[serde(default)]
+    pub total: u64,
+    pub next: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum TrackIsrcResponse {
+    Error(DeezerErrorResponse),
+    Track(TrackIsrc),
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct TrackSearchArtist {
+    pub name: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct TrackSearchResult {
     pub id: u64,
     pub title: String,
-    pub link: String,
-    pub artist: String,
+    pub artist: TrackSearchArtist,
+}
+
+// a page of /search/track, the best matches first
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct TrackSearchPage {
+    #[serde(default)]
+    pub data: Vec<TrackSearchResult>,
 }
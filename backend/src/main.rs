@@ -1,8 +1,17 @@
-use std::env;
+// the chain of warp filters of the routes is deeply nested
+#![recursion_limit = "256"]
+#![allow(
+    clippy::needless_return,
+    clippy::module_inception,
+    clippy::enum_variant_names
+)]
 
-use log::info;
+use std::{env, process};
+
+use log::{error, info};
 
 mod api;
+mod cli;
 mod common;
 mod domain;
 mod infrastructure;
@@ -10,9 +19,22 @@ mod infrastructure;
 #[tokio::main]
 async fn main() {
     env_logger::init();
-    for arg in env::args().into_iter() {
+    for arg in env::args() {
         info!("arg : {}", arg);
     }
+    if let Err(failures) = infrastructure::deezer::init_deezer_config() {
+        error!("Invalid configuration of the deezer queries :\n{}", failures);
+        process::exit(1);
+    }
+    let command_args = cli::commands::get_command_args(env::args().collect());
+    if !command_args.is_empty() {
+        process::exit(cli::commands::run_command(&command_args).await);
+    }
+    domain::collection_watcher::start_collection_watcher();
+    if let Err(failures) = domain::refresh_scheduler::start_refresh_scheduler() {
+        error!("Invalid configuration of the refresh scheduler :\n{}", failures);
+        process::exit(1);
+    }
     let routes = api::routes::build_routes();
     info!("Server started at http://localhost:8000");
     warp::serve(routes).run(([0, 0, 0, 0], 8000)).await;
@@ -1,5 +1,9 @@
 use super::schema::collections;
 use crate::schema::collection_dependencies;
+use crate::schema::collection_exclusions;
+use crate::schema::collection_tags;
+use crate::schema::refresh_runs;
+use crate::schema::track_isrcs;
 use diesel::prelude::*;
 
 #[derive(Insertable)]
@@ -8,6 +12,10 @@ pub struct NewCollection<'a> {
     pub name: &'a str,
     pub url: &'a str,
     pub deezer_id: &'a str,
+    pub created_at: &'a i64,
+    pub track_count: &'a i32,
+    pub origin: &'a str,
+    pub owner_id: Option<&'a str>,
 }
 
 #[derive(Queryable, Selectable)]
@@ -18,6 +26,22 @@ pub struct Collection {
     pub name: String,
     pub url: String,
     pub deezer_id: String,
+    pub kind: String,
+    pub rule: Option<String>,
+    pub track_order: String,
+    pub sort_whole_playlist: bool,
+    pub description: Option<String>,
+    pub created_at: Option<i64>,
+    pub last_refreshed_at: Option<i64>,
+    pub last_refresh_status: Option<String>,
+    pub last_refresh_error: Option<String>,
+    pub last_refresh_tracks_added: Option<i32>,
+    pub track_count: Option<i32>,
+    pub origin: String,
+    pub owner_id: Option<String>,
+    pub checksum: Option<String>,
+    pub refresh_enabled: bool,
+    pub refresh_schedule: String,
 }
 
 #[derive(Insertable)]
@@ -25,6 +49,7 @@ pub struct Collection {
 pub struct NewCollectionDependency<'a> {
     pub parent_id: &'a i32,
     pub child_id: &'a i32,
+    pub filter: Option<&'a str>,
 }
 
 #[derive(Queryable, Selectable)]
@@ -33,4 +58,62 @@ pub struct NewCollectionDependency<'a> {
 pub struct CollectionDependencies {
     pub parent_id: i32,
     pub child_id: i32,
+    pub filter: Option<String>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = collection_exclusions)]
+pub struct NewCollectionExclusion<'a> {
+    pub collection_id: &'a i32,
+    pub kind: &'a str,
+    pub value: &'a str,
+}
+
+#[derive(Queryable, Selectable)]
+#[diesel(table_name = collection_exclusions)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct CollectionExclusion {
+    pub kind: String,
+    pub value: String,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = collection_tags)]
+pub struct NewCollectionTag<'a> {
+    pub collection_id: &'a i32,
+    pub tag: &'a str,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = track_isrcs)]
+pub struct NewTrackIsrc<'a> {
+    pub deezer_id: &'a str,
+    pub isrc: &'a str,
+}
+
+#[derive(Queryable, Selectable)]
+#[diesel(table_name = track_isrcs)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct TrackIsrc {
+    pub deezer_id: String,
+    pub isrc: String,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = refresh_runs)]
+pub struct NewRefreshRun<'a> {
+    pub started_at: &'a i64,
+    pub finished_at: Option<&'a i64>,
+    pub status: &'a str,
+}
+
+#[derive(Queryable, Selectable)]
+#[diesel(table_name = refresh_runs)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct RefreshRun {
+    pub id: i32,
+    pub started_at: i64,
+    pub finished_at: Option<i64>,
+    pub status: String,
+    pub error: Option<String>,
 }
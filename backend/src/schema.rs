@@ -5,6 +5,24 @@ diesel::table! {
         id -> Integer,
         parent_id -> Integer,
         child_id -> Integer,
+        filter -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    collection_exclusions (id) {
+        id -> Integer,
+        collection_id -> Integer,
+        kind -> Text,
+        value -> Text,
+    }
+}
+
+diesel::table! {
+    collection_tags (id) {
+        id -> Integer,
+        collection_id -> Integer,
+        tag -> Text,
     }
 }
 
@@ -14,10 +32,51 @@ diesel::table! {
         deezer_id -> Text,
         name -> Text,
         url -> Text,
+        kind -> Text,
+        rule -> Nullable<Text>,
+        track_order -> Text,
+        sort_whole_playlist -> Bool,
+        description -> Nullable<Text>,
+        created_at -> Nullable<BigInt>,
+        last_refreshed_at -> Nullable<BigInt>,
+        last_refresh_status -> Nullable<Text>,
+        last_refresh_error -> Nullable<Text>,
+        last_refresh_tracks_added -> Nullable<Integer>,
+        track_count -> Nullable<Integer>,
+        origin -> Text,
+        owner_id -> Nullable<Text>,
+        checksum -> Nullable<Text>,
+        refresh_enabled -> Bool,
+        refresh_schedule -> Text,
+    }
+}
+
+diesel::table! {
+    refresh_runs (id) {
+        id -> Integer,
+        started_at -> BigInt,
+        finished_at -> Nullable<BigInt>,
+        status -> Text,
+        error -> Nullable<Text>,
     }
 }
 
+diesel::table! {
+    track_isrcs (id) {
+        id -> Integer,
+        deezer_id -> Text,
+        isrc -> Text,
+    }
+}
+
+diesel::joinable!(collection_exclusions -> collections (collection_id));
+diesel::joinable!(collection_tags -> collections (collection_id));
+
 diesel::allow_tables_to_appear_in_same_query!(
     collection_dependencies,
+    collection_exclusions,
+    collection_tags,
     collections,
+    refresh_runs,
+    track_isrcs,
 );